@@ -0,0 +1,479 @@
+use crate::helper::{
+    path_source, type_path_expr, Symbol, AUTO_PATH, CHUNK, CHUNK_TY, ENV, EXPR, FN_PATH, FROM, INTO, MOD_PATH, PATH, QUOTE, RAW, REDACT, REIFY,
+    REMOTE, RENAME, SKIP, SPAN, TARGET, TARGET_TY,
+    TEMPLATE, TRANSPARENT, VIA_STR, WITH,
+};
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::meta::ParseNestedMeta;
+use syn::{
+    Attribute, Error, Expr, ExprLit, ExprPath, Ident, Lit, LitInt, LitStr, Member, Meta, Path, Type,
+};
+
+/// Stores an attribute value, rejecting a second occurrence.
+struct Attr<T> {
+    name: Symbol,
+    value: Option<T>,
+}
+
+impl<T> Attr<T> {
+    fn none(name: Symbol) -> Self {
+        Attr { name, value: None }
+    }
+
+    fn set(&mut self, meta: &ParseNestedMeta, value: T) -> Result<(), Error> {
+        if self.value.is_some() {
+            return Err(meta.error(format!("duplicate quote attribute `{}`", self.name)));
+        }
+        self.value = Some(value);
+
+        Ok(())
+    }
+
+    fn get(self) -> Option<T> {
+        self.value
+    }
+}
+
+fn parse_lit_str<T: syn::parse::Parse>(meta: &ParseNestedMeta) -> Result<T, Error> {
+    meta.value()?.parse::<LitStr>()?.parse()
+}
+
+/// `name = path::to::item` or `name = "path::to::item"`.
+pub(crate) fn parse_path(meta: &ParseNestedMeta) -> Result<Path, Error> {
+    let value = meta.value()?;
+    if value.peek(LitStr) {
+        value.parse::<LitStr>()?.parse()
+    } else {
+        value.parse()
+    }
+}
+
+/// `via_str` or `via_str = "const"`, which is `true` for the checked `const` flavour.
+fn parse_via_str(meta: &ParseNestedMeta) -> Result<bool, Error> {
+    if !meta.input.peek(syn::Token![=]) {
+        return Ok(false);
+    }
+
+    let flavour: LitStr = meta.value()?.parse()?;
+    if flavour.value() != "const" {
+        return Err(Error::new_spanned(flavour, "expected `via_str` or `via_str = \"const\"`"));
+    }
+
+    Ok(true)
+}
+
+/// `redact` or `redact = "default"`, which is `true` for the `Default::default()` placeholder.
+fn parse_redact(meta: &ParseNestedMeta) -> Result<bool, Error> {
+    if !meta.input.peek(syn::Token![=]) {
+        return Ok(false);
+    }
+
+    let placeholder: LitStr = meta.value()?.parse()?;
+    if placeholder.value() != "default" {
+        return Err(Error::new_spanned(placeholder, "expected `redact` or `redact = \"default\"`"));
+    }
+
+    Ok(true)
+}
+
+/// `skip` or `skip = "compile_error"`, which is `true` for the `compile_error!` flavour.
+fn parse_skip(meta: &ParseNestedMeta) -> Result<bool, Error> {
+    if !meta.input.peek(syn::Token![=]) {
+        return Ok(false);
+    }
+
+    let flavour: LitStr = meta.value()?.parse()?;
+    if flavour.value() != "compile_error" {
+        return Err(Error::new_spanned(flavour, "expected `skip` or `skip = \"compile_error\"`"));
+    }
+
+    Ok(true)
+}
+
+/// `into = "Repr"` or `from = "Repr"`: the value is converted into `Repr` before quoting.
+pub struct Convert {
+    pub repr: Type,
+    /// Emit `<Target as From<Repr>>::from(..)` instead of the bare `Repr`.
+    pub from: bool,
+}
+
+/// `#[quote(...)]` attributes of a field.
+#[derive(Default)]
+pub struct FieldAttrs {
+    pub chunk: Option<usize>,
+    pub chunk_ty: Option<Type>,
+    pub reify: bool,
+    pub raw: bool,
+    pub with: Option<ExprPath>,
+    pub convert: Option<Convert>,
+    /// Expression emitted instead of the value, with `#field` placeholders.
+    pub expr: Option<LitStr>,
+    /// Emit the value through its `Display` form; `true` when the round trip is checked.
+    pub via_str: Option<bool>,
+    /// Environment variable read where the value is used, instead of the value.
+    pub env: Option<LitStr>,
+    /// Never emit the value; `true` emits `Default::default()` instead of a compile error.
+    pub redact: Option<bool>,
+    /// Path of the function a `fn` pointer field is emitted as.
+    pub fn_path: Option<Path>,
+    /// Type the value is emitted as in the `target` type.
+    pub target_ty: Option<Type>,
+}
+
+impl FieldAttrs {
+    pub fn from_ast(attrs: &[Attribute]) -> Result<Self, Error> {
+        let mut chunk = Attr::none(CHUNK);
+        let mut chunk_ty = Attr::none(CHUNK_TY);
+        let mut reify = Attr::none(REIFY);
+        let mut raw = Attr::none(RAW);
+        let mut with = Attr::none(WITH);
+        let mut convert = Attr::none(INTO);
+        let mut expr = Attr::none(EXPR);
+        let mut via_str = Attr::none(VIA_STR);
+        let mut env = Attr::none(ENV);
+        let mut redact = Attr::none(REDACT);
+        let mut fn_path = Attr::none(FN_PATH);
+        let mut target_ty = Attr::none(TARGET_TY);
+        // Attributes that each decide how the field is emitted, so only one may be used.
+        let mut modes: Vec<(Symbol, Path)> = Vec::new();
+
+        for attr in attrs.iter().filter(|attr| attr.path() == QUOTE) {
+            attr.parse_nested_meta(|meta| {
+                if meta.path == CHUNK {
+                    let size: usize = meta.value()?.parse::<LitInt>()?.base10_parse()?;
+                    if size == 0 {
+                        return Err(meta.error("`chunk` must be greater than 0"));
+                    }
+                    modes.push((CHUNK, meta.path.clone()));
+                    chunk.set(&meta, size)
+                } else if meta.path == CHUNK_TY {
+                    chunk_ty.set(&meta, parse_lit_str(&meta)?)
+                } else if meta.path == REIFY {
+                    modes.push((REIFY, meta.path.clone()));
+                    reify.set(&meta, ())
+                } else if meta.path == RAW {
+                    modes.push((RAW, meta.path.clone()));
+                    raw.set(&meta, ())
+                } else if meta.path == WITH {
+                    modes.push((WITH, meta.path.clone()));
+                    with.set(&meta, parse_lit_str(&meta)?)
+                } else if meta.path == INTO || meta.path == FROM {
+                    let from = meta.path == FROM;
+                    modes.push((if from { FROM } else { INTO }, meta.path.clone()));
+                    convert.set(&meta, Convert { repr: parse_lit_str(&meta)?, from })
+                } else if meta.path == EXPR {
+                    modes.push((EXPR, meta.path.clone()));
+                    expr.set(&meta, meta.value()?.parse()?)
+                } else if meta.path == VIA_STR {
+                    modes.push((VIA_STR, meta.path.clone()));
+                    via_str.set(&meta, parse_via_str(&meta)?)
+                } else if meta.path == ENV {
+                    modes.push((ENV, meta.path.clone()));
+                    let name: LitStr = meta.value()?.parse()?;
+                    if name.value().is_empty() {
+                        return Err(Error::new_spanned(name, "`env` needs a variable name"));
+                    }
+                    env.set(&meta, name)
+                } else if meta.path == REDACT {
+                    modes.push((REDACT, meta.path.clone()));
+                    redact.set(&meta, parse_redact(&meta)?)
+                } else if meta.path == FN_PATH {
+                    modes.push((FN_PATH, meta.path.clone()));
+                    fn_path.set(&meta, parse_lit_str(&meta)?)
+                } else if meta.path == TARGET_TY {
+                    modes.push((TARGET_TY, meta.path.clone()));
+                    target_ty.set(&meta, parse_lit_str(&meta)?)
+                } else {
+                    Err(meta.error("unknown quote field attribute"))
+                }
+            })?;
+        }
+
+        if let [(first, _), (second, path), ..] = modes.as_slice() {
+            if first != second {
+                return Err(Error::new_spanned(
+                    path,
+                    format!("`{}` can't be combined with `{}`", second, first),
+                ));
+            }
+        }
+
+        let attrs = FieldAttrs {
+            chunk: chunk.get(),
+            chunk_ty: chunk_ty.get(),
+            reify: reify.get().is_some(),
+            raw: raw.get().is_some(),
+            with: with.get(),
+            convert: convert.get(),
+            expr: expr.get(),
+            via_str: via_str.get(),
+            env: env.get(),
+            redact: redact.get(),
+            fn_path: fn_path.get(),
+            target_ty: target_ty.get(),
+        };
+
+        if let (None, Some(chunk_ty)) = (&attrs.chunk, &attrs.chunk_ty) {
+            return Err(Error::new_spanned(chunk_ty, "`chunk_ty` requires `chunk`"));
+        }
+
+        Ok(attrs)
+    }
+}
+
+/// `#[quote(...)]` attributes of a struct or enum, and the legacy `#[mod_path = "..."]`.
+#[derive(Default)]
+pub struct ContainerAttrs {
+    pub span: Option<Member>,
+    /// Module path the type is emitted under, from `path` or `mod_path`.
+    pub path: Option<Path>,
+    /// Crate name replacing the leading segment of `module_path!()`.
+    pub auto_path: Option<String>,
+    /// Foreign type this type mirrors, with `quote_remote` emitting the foreign type.
+    pub remote: Option<Path>,
+    /// Emit the single field instead of the type.
+    pub transparent: bool,
+    /// Quote the conversion of the value instead of the value itself.
+    pub convert: Option<Convert>,
+    /// Tokens emitted in place of the constructor, with `#field` placeholders.
+    pub template: Option<LitStr>,
+    /// Emit the value through its `Display` form; `true` when the round trip is checked.
+    pub via_str: Option<bool>,
+    /// Type emitted instead of this one, which has the same shape.
+    pub target: Option<Path>,
+}
+
+impl ContainerAttrs {
+    pub fn from_ast(attrs: &[Attribute]) -> Result<Self, Error> {
+        let mut span = Attr::none(SPAN);
+        let mut path = Attr::none(PATH);
+        let mut auto_path = Attr::none(AUTO_PATH);
+        let mut remote = Attr::none(REMOTE);
+        let mut transparent = Attr::none(TRANSPARENT);
+        let mut convert: Attr<(Symbol, Path, Convert)> = Attr::none(INTO);
+        let mut template = Attr::none(TEMPLATE);
+        let mut via_str = Attr::none(VIA_STR);
+        let mut target = Attr::none(TARGET);
+        let mut mod_path = None;
+
+        for attr in attrs {
+            if attr.path() == MOD_PATH {
+                if mod_path.is_some() {
+                    return Err(Error::new_spanned(attr, "duplicate `mod_path` attribute"));
+                }
+                mod_path = Some(parse_mod_path(attr)?);
+            } else if attr.path() == QUOTE {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path == SPAN {
+                        span.set(&meta, meta.value()?.parse()?)
+                    } else if meta.path == PATH {
+                        path.set(&meta, validate_path(parse_path(&meta)?)?)
+                    } else if meta.path == AUTO_PATH {
+                        let crate_name = if meta.input.peek(syn::Token![=]) {
+                            let crate_name: LitStr = meta.value()?.parse()?;
+                            crate_name.parse::<syn::Ident>()?;
+                            crate_name.value()
+                        } else {
+                            "crate".to_string()
+                        };
+                        auto_path.set(&meta, crate_name)
+                    } else if meta.path == REMOTE {
+                        remote.set(&meta, validate_path(parse_lit_str(&meta)?)?)
+                    } else if meta.path == TRANSPARENT {
+                        transparent.set(&meta, meta.path.clone())
+                    } else if meta.path == INTO || meta.path == FROM {
+                        let repr = parse_lit_str(&meta)?;
+                        let from = meta.path == FROM;
+                        let name = if from { FROM } else { INTO };
+                        if let Some((previous, ..)) = &convert.value {
+                            if *previous != name {
+                                return Err(meta.error(format!("`{}` can't be combined with `{}`", name, previous)));
+                            }
+                        }
+                        convert.set(&meta, (name, meta.path.clone(), Convert { repr, from }))
+                    } else if meta.path == TEMPLATE {
+                        template.set(&meta, meta.value()?.parse()?)
+                    } else if meta.path == VIA_STR {
+                        via_str.set(&meta, (meta.path.clone(), parse_via_str(&meta)?))
+                    } else if meta.path == TARGET {
+                        target.set(&meta, validate_path(parse_lit_str(&meta)?)?)
+                    } else {
+                        Err(meta.error("unknown quote container attribute"))
+                    }
+                })?;
+            }
+        }
+
+        let path = match (path.get(), mod_path) {
+            (Some(path), Some(_)) => {
+                return Err(Error::new_spanned(path, "`path` can't be combined with `mod_path`"))
+            }
+            (path, mod_path) => path.or(mod_path),
+        };
+        let auto_path = auto_path.get();
+        if let (Some(_), Some(path)) = (&auto_path, &path) {
+            return Err(Error::new_spanned(path, "`auto_path` can't be combined with a path"));
+        }
+
+        let remote = remote.get();
+        if let Some(remote) = &remote {
+            if path.is_some() || auto_path.is_some() {
+                return Err(Error::new_spanned(remote, "`remote` can't be combined with `path` or `auto_path`"));
+            }
+        }
+        let span = span.get();
+        if let (Some(_), Some(span)) = (&remote, &span) {
+            return Err(Error::new_spanned(span, "`span` can't be combined with `remote`"));
+        }
+
+        let transparent = transparent.get();
+        if let (Some(_), Some(transparent)) = (&remote, &transparent) {
+            return Err(Error::new_spanned(transparent, "`transparent` can't be combined with `remote`"));
+        }
+
+        let convert = convert.get();
+        if let Some((_, convert, _)) = &convert {
+            if remote.is_some() || span.is_some() || transparent.is_some() {
+                return Err(Error::new_spanned(
+                    convert,
+                    format!("`{}` can't be combined with `remote`, `span` or `transparent`", convert.to_token_stream()),
+                ));
+            }
+        }
+
+        let template: Option<LitStr> = template.get();
+        if let Some(template) = &template {
+            if remote.is_some() || transparent.is_some() || convert.is_some() {
+                return Err(Error::new_spanned(
+                    template,
+                    "`template` can't be combined with `remote`, `transparent`, `into` or `from`",
+                ));
+            }
+        }
+
+        let via_str: Option<(Path, bool)> = via_str.get();
+        if let Some((via_str, _)) = &via_str {
+            if remote.is_some() || span.is_some() || transparent.is_some() || convert.is_some() || template.is_some() {
+                return Err(Error::new_spanned(
+                    via_str,
+                    "`via_str` can't be combined with `remote`, `span`, `transparent`, `into`, `from` or `template`",
+                ));
+            }
+        }
+
+        let target: Option<Path> = target.get();
+        if let Some(target) = &target {
+            if path.is_some()
+                || auto_path.is_some()
+                || remote.is_some()
+                || transparent.is_some()
+                || convert.is_some()
+                || via_str.is_some()
+            {
+                return Err(Error::new_spanned(
+                    target,
+                    "`target` can't be combined with a path, `remote`, `transparent`, `into`, `from` or `via_str`",
+                ));
+            }
+        }
+
+        Ok(ContainerAttrs {
+            span,
+            path,
+            auto_path,
+            remote,
+            transparent: transparent.is_some(),
+            convert: convert.map(|(.., convert)| convert),
+            template,
+            via_str: via_str.map(|(_, checked)| checked),
+            target,
+        })
+    }
+
+    /// Expression of type `&str` naming the type `name` is emitted as.
+    pub fn type_path(&self, name: &Ident) -> TokenStream {
+        match &self.target {
+            Some(target) => {
+                let target = path_source(target);
+                quote::quote! {#target}
+            }
+            None => type_path_expr(&self.path, &self.auto_path, name),
+        }
+    }
+}
+
+pub struct VariantAttrs {
+    /// The variant can't be quoted; `true` when it emits `compile_error!` instead of failing.
+    pub skip: Option<bool>,
+    /// Name the variant is emitted under.
+    pub rename: Option<Ident>,
+    /// Module path the variant is emitted under instead of the enum's path.
+    pub path: Option<Path>,
+}
+
+impl VariantAttrs {
+    pub fn from_ast(attrs: &[Attribute]) -> Result<Self, Error> {
+        let mut skip = Attr::none(SKIP);
+        let mut rename = Attr::none(RENAME);
+        let mut path = Attr::none(PATH);
+
+        for attr in attrs {
+            if attr.path() == QUOTE {
+                attr.parse_nested_meta(|meta| {
+                    if meta.path == SKIP {
+                        skip.set(&meta, (meta.path.clone(), parse_skip(&meta)?))
+                    } else if meta.path == RENAME {
+                        rename.set(&meta, parse_lit_str(&meta)?)
+                    } else if meta.path == PATH {
+                        path.set(&meta, validate_path(parse_path(&meta)?)?)
+                    } else {
+                        Err(meta.error("unknown quote variant attribute"))
+                    }
+                })?;
+            }
+        }
+
+        let skip: Option<(Path, bool)> = skip.get();
+        let rename: Option<Ident> = rename.get();
+        let path = path.get();
+        if let Some((skip, _)) = &skip {
+            if rename.is_some() || path.is_some() {
+                return Err(Error::new_spanned(skip, "`skip` can't be combined with `rename` or `path`"));
+            }
+        }
+
+        Ok(VariantAttrs {
+            skip: skip.map(|(_, compile_error)| compile_error),
+            rename,
+            path,
+        })
+    }
+}
+
+/// Parses `#[mod_path = "path::to::mod"]`, which must hold a module path.
+fn parse_mod_path(attr: &Attribute) -> Result<Path, Error> {
+    let value = match &attr.meta {
+        Meta::NameValue(name_value) => &name_value.value,
+        _ => return Err(Error::new_spanned(attr, "Attribute `mod_path` must be a named value.")),
+    };
+
+    match value {
+        Expr::Lit(ExprLit { lit: Lit::Str(path), .. }) => path
+            .parse()
+            .map_err(|_| Error::new_spanned(path, "Value of `mod_path` must be a path of mod"))
+            .and_then(validate_path),
+        _ => Err(Error::new_spanned(value, "`mod_path` must be a string")),
+    }
+}
+
+/// Module paths can't have generic arguments.
+fn validate_path(path: Path) -> Result<Path, Error> {
+    match path.segments.iter().find(|segment| !segment.arguments.is_none()) {
+        Some(segment) => Err(Error::new_spanned(
+            &segment.arguments,
+            "module path can't have generic arguments",
+        )),
+        None => Ok(path),
+    }
+}
@@ -0,0 +1,148 @@
+use crate::attr::Convert;
+use crate::helper::{
+    resolve_path_statement, source_where_clause, to_tokens_impls, write_path_statement, Interpolated,
+};
+use helpers::get_wrapped_value;
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use syn::{Error, Generics, Ident};
+
+/// Expression tokenizing `value`, a reference, as its conversion into `convert.repr`.
+///
+/// With `from`, the tokens rebuild `target` from the representation; `resolve_target` is run
+/// first, so `target` can interpolate a resolved path.
+pub fn convert_tokens(
+    convert: &Convert,
+    value: &TokenStream,
+    resolve_target: &TokenStream,
+    target: &TokenStream,
+) -> Result<TokenStream, Error> {
+    let repr = &convert.repr;
+    let wrapped = get_wrapped_value(repr, quote::quote! {__quote_data_repr}, true)?;
+    let repr_tokens = Interpolated("__quote_data_repr_tokens".to_string());
+    let tokens = if convert.from {
+        quote::quote! {
+            #resolve_target
+            quote::quote_spanned! {quote_data::current_span()=>
+                <#target as ::core::convert::From<#repr>>::from(#repr_tokens)
+            }
+        }
+    } else {
+        quote::quote! {
+            quote::ToTokens::into_token_stream(__quote_data_repr_tokens)
+        }
+    };
+
+    Ok(quote::quote! {
+        {
+            let __quote_data_repr: #repr = ::core::convert::Into::into(#value);
+            let __quote_data_repr_tokens = #wrapped;
+            #tokens
+        }
+    })
+}
+
+/// Statement writing the source of `value`, a reference, as its conversion into
+/// `convert.repr`. With `from`, `write_target` writes the type rebuilt from it.
+pub fn convert_source(convert: &Convert, value: &TokenStream, write_target: &TokenStream) -> TokenStream {
+    let repr = &convert.repr;
+    let (open, close) = if convert.from {
+        let open = format!(" as ::core::convert::From<{}>>::from(", quote::quote! {#repr});
+        (
+            quote::quote! {
+                out.write_str("<")?;
+                #write_target
+                out.write_str(#open)?;
+            },
+            quote::quote! {out.write_str(")")?;},
+        )
+    } else {
+        (TokenStream::new(), TokenStream::new())
+    };
+
+    quote::quote! {
+        {
+            let __quote_data_repr: #repr = ::core::convert::Into::into(#value);
+            #open
+            quote_data::ToRustSource::write_source(&__quote_data_repr, out)?;
+            #close
+        }
+    }
+}
+
+/// `ToTokens` and `ToRustSource` of a struct or enum with `into` or `from`, which quote its
+/// conversion; `type_path` is the type rebuilt with `from`.
+pub fn convert_implement(
+    name: &Ident,
+    generics: &Generics,
+    convert: &Convert,
+    type_path: &TokenStream,
+) -> Result<TokenStream, Error> {
+    let tokens = convert_tokens(
+        convert,
+        &quote::quote! {self},
+        &resolve_path_statement(type_path),
+        &Interpolated("__quote_data_path".to_string()).into_token_stream(),
+    )?;
+    let source = convert_source(convert, &quote::quote! {self}, &write_path_statement(type_path));
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let source_where_clause = source_where_clause(generics);
+
+    let to_tokens_impls = to_tokens_impls(
+        &impl_generics.to_token_stream(),
+        &quote::quote! {#name #ty_generics},
+        &where_clause.to_token_stream(),
+        &quote::quote! {
+            quote_data::share_tokens(#tokens).to_tokens(tokens);
+        },
+    );
+
+    Ok(quote::quote! {
+        #to_tokens_impls
+
+        impl #impl_generics quote_data::ToRustSource for #name #ty_generics #source_where_clause {
+            fn write_source(&self, out: &mut dyn ::core::fmt::Write) -> ::core::fmt::Result {
+                #source
+                Ok(())
+            }
+        }
+    })
+}
+
+/// `ToTokens` and `ToRustSource` of a struct or enum with `via_str`, which quote its `Display`
+/// form; `type_path` is the type parsed from it.
+pub fn via_str_implement(name: &Ident, generics: &Generics, checked: bool, type_path: &TokenStream) -> TokenStream {
+    let value = if checked {
+        let path = name.to_string();
+        quote::quote! {
+            quote_data::TokenizableViaStr::checked(self, &__quote_data_path, #path)
+        }
+    } else {
+        quote::quote! {
+            quote_data::TokenizableViaStr::new(self, &__quote_data_path)
+        }
+    };
+    let (impl_generics, ty_generics, where_clause) = generics.split_for_impl();
+    let source_where_clause = source_where_clause(generics);
+
+    let to_tokens_impls = to_tokens_impls(
+        &impl_generics.to_token_stream(),
+        &quote::quote! {#name #ty_generics},
+        &where_clause.to_token_stream(),
+        &quote::quote! {
+            let __quote_data_path = quote_data::QuoteContext::with(|context| context.rewrite_path(#type_path));
+            quote_data::share_tokens(quote::ToTokens::into_token_stream(#value)).to_tokens(tokens);
+        },
+    );
+
+    quote::quote! {
+        #to_tokens_impls
+
+        impl #impl_generics quote_data::ToRustSource for #name #ty_generics #source_where_clause {
+            fn write_source(&self, out: &mut dyn ::core::fmt::Write) -> ::core::fmt::Result {
+                let __quote_data_path = quote_data::QuoteContext::with(|context| context.rewrite_path(#type_path));
+                quote_data::ToRustSource::write_source(&#value, out)
+            }
+        }
+    }
+}
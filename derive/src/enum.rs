@@ -1,21 +1,25 @@
-use crate::helper::Interpolated;
-use crate::r#struct::StructField;
+use crate::attr::{ContainerAttrs, VariantAttrs};
+use crate::convert::{convert_implement, via_str_implement};
+use crate::helper::{
+    path_source, resolve_path_statement, source_where_clause, to_tokens_impls, write_path_statement,
+    Interpolated,
+};
+use crate::r#struct::{check_secret_fields, field_source, parse_field_exprs, temp_value_statements, StructField};
 use heck::ToSnakeCase;
-use helpers::get_wrapped_value;
 use proc_macro2::TokenStream;
 use quote::{format_ident, quote, ToTokens};
-use syn::{Token, Data, DeriveInput, Error, Fields, Ident, Variant as SynVariant, Generics, GenericParam};
+use syn::{Token, Data, DeriveInput, Error, Fields, Ident, Variant as SynVariant, Generics, GenericParam, Path};
 use syn::punctuated::Punctuated;
 
 pub struct EnumStructure {
     name: Ident,
     variants: Vec<Variant>,
     generics: Generics,
-    mod_path: Option<TokenStream>,
+    attrs: ContainerAttrs,
 }
 
 impl EnumStructure {
-    pub fn from_ast(input: &DeriveInput, mod_path: Option<TokenStream>) -> Result<Self, Error> {
+    pub fn from_ast(input: &DeriveInput) -> Result<Self, Error> {
         let name = input.ident.clone();
 
         let data_enum = match &input.data {
@@ -26,25 +30,74 @@ impl EnumStructure {
         let variants = data_enum
             .variants
             .iter()
-            .map(Variant::from_ast)
+            .map(|variant| Variant::from_ast(variant, &name))
             .collect::<Result<Vec<Variant>, Error>>()?;
 
         let generics = input.generics.clone();
+        let attrs = ContainerAttrs::from_ast(&input.attrs)?;
+        if attrs.transparent {
+            if let Some(variant) = data_enum.variants.iter().find(|variant| variant.fields.len() != 1) {
+                return Err(Error::new_spanned(
+                    &variant.ident,
+                    "variants of `transparent` enums must have exactly one field",
+                ));
+            }
+        }
+        if attrs.transparent {
+            if let Some(variant) = variants.iter().find(|variant| {
+                variant.attrs.rename.is_some() || variant.attrs.path.is_some()
+            }) {
+                return Err(Error::new_spanned(
+                    &variant.name,
+                    "`rename` and `path` have no effect on variants of `transparent` enums",
+                ));
+            }
+        }
+        if attrs.target.is_none() {
+            if let Some(target_ty) = variants
+                .iter()
+                .flat_map(|variant| &variant.fields)
+                .find_map(StructField::target_ty)
+            {
+                return Err(Error::new_spanned(target_ty, "`target_ty` requires a `target` type"));
+            }
+        }
+        check_secret_fields(variants.iter().flat_map(|variant| &variant.fields), &attrs)?;
+        if let Some(span) = &attrs.span {
+            return Err(Error::new_spanned(span, "`span` is only supported on structs"));
+        }
+        if let Some(template) = &attrs.template {
+            return Err(Error::new_spanned(template, "`template` is only supported on structs"));
+        }
 
         Ok(EnumStructure {
             name,
             variants,
             generics,
-            mod_path
+            attrs,
         })
     }
 
     pub fn get_implement(&self) -> Result<TokenStream, Error> {
+        if let Some(remote) = &self.attrs.remote {
+            return self.remote_implement(remote);
+        }
+
         let name = &self.name;
+        let type_path = self.attrs.type_path(name);
+        if let Some(convert) = &self.attrs.convert {
+            return convert_implement(name, &self.generics, convert, &type_path);
+        }
+        if let Some(checked) = self.attrs.via_str {
+            return Ok(via_str_implement(name, &self.generics, checked, &type_path));
+        }
+        let pattern_path = quote! {#name};
         let variants: Vec<TokenStream> = self
             .variants
             .iter()
-            .map(|variant| variant.arm_token_stream(&self.name, &self.mod_path))
+            .map(|variant| {
+                variant.arm_token_stream(&self.name, &pattern_path, &type_path, self.attrs.transparent)
+            })
             .collect::<Result<Vec<_>, _>>()?;
 
         let generics = &self.generics.params;
@@ -67,14 +120,60 @@ impl EnumStructure {
                 }
             ).collect();
         let where_clause = &self.generics.where_clause;
+        let source_where_clause = source_where_clause(&self.generics);
+        let source_variants: Vec<TokenStream> = self
+            .variants
+            .iter()
+            .map(|variant| variant.source_arm_token_stream(&self.name, &type_path, self.attrs.transparent))
+            .collect();
+
+        let to_tokens_impls = to_tokens_impls(
+            &quote! {<#generics>},
+            &quote! {#name <#generics_without_bounds>},
+            &where_clause.to_token_stream(),
+            &quote! {
+                quote_data::share_tokens(match self {
+                        #(#variants),*
+                }).to_tokens(tokens);
+            },
+        );
 
         Ok(quote! {
-            impl<#generics> quote::ToTokens for #name <#generics_without_bounds> #where_clause {
-                fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
-                    use quote_data::Tokenizable;
+            #to_tokens_impls
+
+            impl<#generics> quote_data::ToRustSource for #name <#generics_without_bounds> #source_where_clause {
+                fn write_source(&self, out: &mut dyn ::core::fmt::Write) -> ::core::fmt::Result {
                     match self {
+                        #(#source_variants),*
+                    }
+                }
+            }
+        })
+    }
+}
+
+impl EnumStructure {
+    /// `quote_remote`, which emits the foreign enum `remote` from a value of it.
+    fn remote_implement(&self, remote: &Path) -> Result<TokenStream, Error> {
+        let name = &self.name;
+        let remote_path = path_source(remote);
+        let type_path = quote! {#remote_path};
+        let pattern_path = quote! {#remote};
+        let variants: Vec<TokenStream> = self
+            .variants
+            .iter()
+            .map(|variant| variant.arm_token_stream(&self.name, &pattern_path, &type_path, false))
+            .collect::<Result<Vec<_>, _>>()?;
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+
+        Ok(quote! {
+            quote_data::__to_tokens_impl! {
+                impl #impl_generics #name #ty_generics #where_clause {
+                    pub fn quote_remote(value: &#remote #ty_generics) -> proc_macro2::TokenStream {
+                        quote_data::share_tokens(match value {
                             #(#variants),*
-                    }.to_tokens(tokens);
+                        })
+                    }
                 }
             }
         })
@@ -85,64 +184,213 @@ struct Variant {
     name: Ident,
     named: bool,
     fields: Vec<StructField>,
+    attrs: VariantAttrs,
 }
 
 impl Variant {
-    pub fn from_ast(variant: &SynVariant) -> Result<Self, Error> {
+    pub fn from_ast(variant: &SynVariant, enum_ident: &Ident) -> Result<Self, Error> {
         let name = variant.ident.clone();
+        let owner = format!("{}::{}", enum_ident, name);
         let named = matches!(variant.fields, Fields::Named(_));
         let fields = variant
             .fields
             .iter()
             .enumerate()
-            .map(|(index, field)| StructField::from_ast(field, index))
-            .collect::<Result<Vec<_>, _>>()?;
+            .map(|(index, field)| StructField::from_ast(field, index, &owner))
+            .collect::<Result<Vec<_>, _>>()
+            .and_then(parse_field_exprs)?;
+        let attrs = VariantAttrs::from_ast(&variant.attrs)?;
 
         Ok(Variant {
             name,
             named,
             fields,
+            attrs,
         })
     }
 
-    pub fn arm_token_stream(
+    /// Name the variant is emitted under.
+    fn emitted_name(&self) -> &Ident {
+        self.attrs.rename.as_ref().unwrap_or(&self.name)
+    }
+
+    /// Source of the variant's own path, for variants with a `path`.
+    fn variant_path(&self) -> Option<String> {
+        self.attrs
+            .path
+            .as_ref()
+            .map(|path| format!("{}::{}", path_source(path), self.emitted_name()))
+    }
+
+    fn skip_message(&self, enum_ident: &Ident) -> String {
+        format!("variant `{}::{}` is skipped and can't be quoted", enum_ident, self.name)
+    }
+
+    fn temp_value_ident(&self, enum_ident: &Ident, field: &StructField) -> Ident {
+        format_ident!(
+            "temp_{}_{}",
+            enum_ident.to_string().to_snake_case(),
+            field.name()
+        )
+    }
+
+    fn argument_ident(&self, enum_ident: &Ident, field: &StructField) -> Ident {
+        format_ident!(
+            "{}_{}",
+            enum_ident.to_string().to_snake_case(),
+            field.name()
+        )
+    }
+
+    fn arguments_token_stream(&self, enum_ident: &Ident) -> TokenStream {
+        let arguments: Vec<TokenStream> = self
+            .fields
+            .iter()
+            .map(|field| {
+                // Fields emitted as their `expr`, an environment variable or a redaction
+                // aren't read.
+                let argument = if field.ignores_value() {
+                    quote! {_}
+                } else {
+                    self.argument_ident(enum_ident, field).to_token_stream()
+                };
+                if let Some(name) = field.ident() {
+                    quote::quote! {
+                        #name: #argument
+                    }
+                } else {
+                    argument.to_token_stream()
+                }
+            })
+            .collect();
+
+        if self.fields.is_empty() {
+            TokenStream::new()
+        } else if self.named {
+            quote! {
+                {#(#arguments),*}
+            }
+        } else {
+            quote! {
+                (#(#arguments),*)
+            }
+        }
+    }
+
+    pub fn source_arm_token_stream(
         &self,
         enum_ident: &Ident,
-        mod_path: &Option<TokenStream>,
-    ) -> Result<TokenStream, Error> {
+        type_path: &TokenStream,
+        transparent: bool,
+    ) -> TokenStream {
         let name = &self.name;
+        if let Some(compile_error) = self.attrs.skip {
+            let message = self.skip_message(enum_ident);
+            return if compile_error {
+                let source = format!("::core::compile_error!({:?})", message);
+                quote! {
+                    #enum_ident::#name {..} => out.write_str(#source)
+                }
+            } else {
+                quote! {
+                    #enum_ident::#name {..} => Err(quote_data::fail_source(#message))
+                }
+            };
+        }
 
-        let mut arguments = vec![];
-        let mut temp_values = vec![];
-        let mut construct_params = vec![];
+        let arguments_tokens = self.arguments_token_stream(enum_ident);
+        let (open, close) = if self.fields.is_empty() {
+            ("", "")
+        } else if self.named {
+            (" { ", " }")
+        } else {
+            ("(", ")")
+        };
+        let (write_path, prefix) = match self.variant_path() {
+            Some(variant_path) => (write_path_statement(&quote! {#variant_path}), open.to_string()),
+            None => (write_path_statement(type_path), format!("::{}{}", self.emitted_name(), open)),
+        };
 
-        for field in self.fields.iter() {
-            let argument = format_ident!(
-                "{}_{}",
-                enum_ident.to_string().to_snake_case(),
-                field.name()
-            );
-            let temp_value_ident = format_ident!(
-                "temp_{}_{}",
-                enum_ident.to_string().to_snake_case(),
-                field.name()
-            );
-
-            let value = get_wrapped_value(field.ty(), argument.to_token_stream(), false, false)?;
+        let fields: Vec<TokenStream> = self
+            .fields
+            .iter()
+            .enumerate()
+            .map(|(index, field)| {
+                let separator = if index != 0 { ", " } else { "" };
+                let label = match field.ident() {
+                    Some(_) if transparent => String::new(),
+                    Some(ident) => format!("{}{}: ", separator, ident),
+                    None => separator.to_string(),
+                };
 
-            let interpolated_param = Interpolated(temp_value_ident.to_string());
+                let write_source = field_source(&self.fields, index, &|field| {
+                    self.argument_ident(enum_ident, field).to_token_stream()
+                });
 
-            arguments.push(if let Some(name) = field.ident() {
-                quote::quote! {
-                    #name: #argument
+                quote! {
+                    out.write_str(#label)?;
+                    #write_source
+                }
+            })
+            .collect();
+
+        if transparent {
+            return quote! {
+                #enum_ident::#name#arguments_tokens => {
+                    #(#fields)*
+                    Ok(())
+                }
+            };
+        }
+
+        quote! {
+            #enum_ident::#name#arguments_tokens => {
+                #write_path
+                out.write_str(#prefix)?;
+                #(#fields)*
+                out.write_str(#close)
+            }
+        }
+    }
+
+    /// Match arm emitting this variant; `pattern_path` is the enum matched on and `type_path`
+    /// the one emitted. Arms of `transparent` enums emit the variant's field instead.
+    pub fn arm_token_stream(
+        &self,
+        enum_ident: &Ident,
+        pattern_path: &TokenStream,
+        type_path: &TokenStream,
+        transparent: bool,
+    ) -> Result<TokenStream, Error> {
+        let name = &self.name;
+        if let Some(compile_error) = self.attrs.skip {
+            let message = self.skip_message(enum_ident);
+            return Ok(if compile_error {
+                quote! {
+                    #pattern_path::#name {..} => quote::quote_spanned! {quote_data::current_span()=>
+                        ::core::compile_error!(#message)
+                    }
                 }
             } else {
-                argument.to_token_stream()
+                quote! {
+                    #pattern_path::#name {..} => quote_data::fail(#message)
+                }
             });
+        }
 
-            temp_values.push(quote::quote! {
-                let #temp_value_ident = #value
-            });
+        let temp_values = temp_value_statements(
+            &self.fields,
+            &|field| self.temp_value_ident(enum_ident, field),
+            &|field| (self.argument_ident(enum_ident, field).to_token_stream(), false),
+        )?;
+        let mut temp_value_idents = vec![];
+        let mut construct_params = vec![];
+
+        for field in self.fields.iter() {
+            let temp_value_ident = self.temp_value_ident(enum_ident, field);
+            let interpolated_param = Interpolated(temp_value_ident.to_string());
+
+            temp_value_idents.push(temp_value_ident.clone());
             construct_params.push(if let Some(name) = field.ident() {
                 quote::quote! {
                     #name: #interpolated_param
@@ -154,25 +402,26 @@ impl Variant {
             })
         }
 
-        let arguments_tokens = if !self.fields.is_empty() {
-            if self.named {
-                quote! {
-                    {#(#arguments),*}
-                }
-            } else {
-                quote! {
-                    (#(#arguments),*)
+        let arguments_tokens = self.arguments_token_stream(enum_ident);
+
+        if transparent {
+            return Ok(quote! {
+                #pattern_path::#name#arguments_tokens => {
+                    #(#temp_values;)*
+                    quote::ToTokens::into_token_stream(#(#temp_value_idents)*)
                 }
+            });
+        }
+
+        let path = Interpolated("__quote_data_path".to_string());
+        let (resolve_path, path) = match self.variant_path() {
+            Some(variant_path) => (resolve_path_statement(&quote! {#variant_path}), quote! {#path}),
+            None => {
+                let emitted_name = self.emitted_name();
+                (resolve_path_statement(type_path), quote! {#path::#emitted_name})
             }
-        } else {
-            TokenStream::new()
         };
 
-        let mod_path_token = mod_path
-            .as_ref()
-            .map(|path| quote! {#path::})
-            .unwrap_or_default();
-
         let construct_token_stream = if self.named {
             quote::quote! {
                 {#(#construct_params),*}
@@ -186,10 +435,11 @@ impl Variant {
         };
 
         Ok(quote! {
-            #enum_ident::#name#arguments_tokens => {
+            #pattern_path::#name#arguments_tokens => {
                 #(#temp_values;)*
-                quote::quote! {
-                    #mod_path_token#enum_ident::#name#construct_token_stream
+                #resolve_path
+                quote::quote_spanned! {quote_data::current_span()=>
+                    #path#construct_token_stream
                 }
             }
         })
@@ -1,7 +1,7 @@
 use proc_macro2::{Punct, Spacing, Span, TokenStream};
 use quote::{ToTokens, TokenStreamExt};
 use std::fmt::{self, Display};
-use syn::{Ident, Path};
+use syn::{parse_quote, Generics, Ident, Path, Type, WhereClause};
 
 pub struct Interpolated(pub String);
 
@@ -12,7 +12,7 @@ impl ToTokens for Interpolated {
     }
 }
 
-#[derive(Copy, Clone)]
+#[derive(Copy, Clone, PartialEq)]
 pub struct Symbol(&'static str);
 
 impl Symbol {
@@ -52,3 +52,151 @@ impl Display for Symbol {
 }
 
 pub const MOD_PATH: Symbol = Symbol("mod_path");
+pub const QUOTE: Symbol = Symbol("quote");
+pub const CHUNK: Symbol = Symbol("chunk");
+pub const CHUNK_TY: Symbol = Symbol("chunk_ty");
+pub const REIFY: Symbol = Symbol("reify");
+pub const RAW: Symbol = Symbol("raw");
+pub const SPAN: Symbol = Symbol("span");
+pub const AUTO_PATH: Symbol = Symbol("auto_path");
+pub const PATH: Symbol = Symbol("path");
+pub const REMOTE: Symbol = Symbol("remote");
+pub const WITH: Symbol = Symbol("with");
+pub const TRANSPARENT: Symbol = Symbol("transparent");
+pub const INTO: Symbol = Symbol("into");
+pub const FROM: Symbol = Symbol("from");
+pub const TEMPLATE: Symbol = Symbol("template");
+pub const EXPR: Symbol = Symbol("expr");
+pub const VIA_STR: Symbol = Symbol("via_str");
+pub const ENV: Symbol = Symbol("env");
+pub const REDACT: Symbol = Symbol("redact");
+pub const FN_PATH: Symbol = Symbol("fn_path");
+pub const SKIP: Symbol = Symbol("skip");
+pub const RENAME: Symbol = Symbol("rename");
+pub const TARGET: Symbol = Symbol("target");
+pub const TARGET_TY: Symbol = Symbol("target_ty");
+
+pub fn source_where_clause(generics: &Generics) -> WhereClause {
+    let mut generics = generics.clone();
+    let type_params: Vec<Ident> = generics
+        .type_params()
+        .map(|param| param.ident.clone())
+        .collect();
+    let where_clause = generics.make_where_clause();
+
+    for ident in type_params {
+        where_clause
+            .predicates
+            .push(parse_quote! {#ident: quote_data::ToRustSource});
+    }
+
+    where_clause.clone()
+}
+
+/// Source of `path` without whitespace.
+///
+/// `$crate` only exists while expanding a macro, so it is emitted as `crate`.
+pub fn path_source(path: &Path) -> String {
+    let path: String = path
+        .to_token_stream()
+        .to_string()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect();
+
+    match path.strip_prefix("$crate") {
+        Some(rest) => format!("crate{}", rest),
+        None => path,
+    }
+}
+
+/// Source of `ty`, keeping whitespace only between two words, e.g. `crate::model::Row` or
+/// `&'a mut T` instead of the token spacing `crate :: model :: Row`.
+pub fn type_source(ty: &Type) -> String {
+    let tokens = ty.to_token_stream().to_string();
+    let is_word = |c: char| c.is_alphanumeric() || c == '_' || c == '\'';
+    let mut source = String::with_capacity(tokens.len());
+    let mut chars = tokens.chars().peekable();
+    while let Some(c) = chars.next() {
+        if !c.is_whitespace() {
+            source.push(c);
+        } else if source.ends_with(is_word) && chars.peek().is_some_and(|next| is_word(*next)) {
+            source.push(' ');
+        }
+    }
+    source
+}
+
+/// Source of `mod_path` followed by `::`, or nothing without one.
+pub fn mod_path_source(mod_path: &Option<Path>) -> String {
+    mod_path
+        .as_ref()
+        .map(|path| format!("{}::", path_source(path)))
+        .unwrap_or_default()
+}
+
+/// Expression of type `&str` naming the type `name` in the generated code, before the runtime
+/// `QuoteContext` path rewrites.
+pub fn type_path_expr(
+    mod_path: &Option<Path>,
+    auto_path: &Option<String>,
+    name: &Ident,
+) -> TokenStream {
+    let name = name.to_string();
+    match auto_path {
+        Some(crate_name) => quote::quote! {
+            &quote_data::auto_path(module_path!(), #crate_name, #name)
+        },
+        None => {
+            let path = format!("{}{}", mod_path_source(mod_path), name);
+            quote::quote! {#path}
+        }
+    }
+}
+
+/// Statement binding `__quote_data_path` to the tokens of `path` after the runtime
+/// `QuoteContext` path rewrites.
+pub fn resolve_path_statement(path: &TokenStream) -> TokenStream {
+    quote::quote! {
+        let __quote_data_path = quote_data::QuoteContext::with(|context| context.resolve_path(#path));
+    }
+}
+
+/// Statement writing `path` after the runtime `QuoteContext` path rewrites.
+pub fn write_path_statement(path: &TokenStream) -> TokenStream {
+    quote::quote! {
+        quote_data::QuoteContext::with(|context| out.write_str(&context.rewrite_path(#path)))?;
+    }
+}
+
+/// `TryQuote` and `ToTokens` impls for `self_ty`, with `body` writing the value into `tokens`,
+/// a `&mut TokenStream`. `ToTokens` panics with the `QuoteError` outside `try_quote`.
+pub fn to_tokens_impls(
+    impl_generics: &TokenStream,
+    self_ty: &TokenStream,
+    where_clause: &TokenStream,
+    body: &TokenStream,
+) -> TokenStream {
+    quote::quote! {
+        quote_data::__to_tokens_impl! {
+            impl #impl_generics quote_data::TryQuote for #self_ty #where_clause {
+                fn try_to_tokens(&self) -> ::core::result::Result<proc_macro2::TokenStream, quote_data::QuoteError> {
+                    use quote::ToTokens as _;
+
+                    quote_data::try_quote(|| {
+                        let mut __quote_data_tokens = proc_macro2::TokenStream::new();
+                        let tokens = &mut __quote_data_tokens;
+                        #body
+                        __quote_data_tokens
+                    })
+                }
+            }
+
+            impl #impl_generics quote::ToTokens for #self_ty #where_clause {
+                fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+                    tokens.extend(quote_data::quote_or_fail(quote_data::TryQuote::try_to_tokens(self)));
+                }
+            }
+        }
+    }
+}
@@ -0,0 +1,125 @@
+use crate::attr::parse_path;
+use crate::helper::{AUTO_PATH, MOD_PATH, PATH, QUOTE, REMOTE, TARGET, TRANSPARENT};
+use proc_macro2::{TokenStream, TokenTree};
+use syn::meta::ParseNestedMeta;
+use syn::{parse_quote, Attribute, Error, Item, ItemMod, Meta, Path};
+
+/// Arguments of `#[quoted_module(path = "...")]`.
+pub struct ModuleArgs {
+    path: Option<Path>,
+}
+
+impl ModuleArgs {
+    pub fn new() -> Self {
+        ModuleArgs { path: None }
+    }
+
+    pub fn parse(&mut self, meta: ParseNestedMeta) -> Result<(), Error> {
+        if meta.path == PATH {
+            if self.path.is_some() {
+                return Err(meta.error("duplicate `path` argument"));
+            }
+            self.path = Some(parse_path(&meta)?);
+
+            Ok(())
+        } else {
+            Err(meta.error("unknown quoted_module argument"))
+        }
+    }
+}
+
+/// Adds `#[derive(QuoteIt)]` and the module's path to every struct and enum in `module`,
+/// including those of nested inline modules.
+pub fn quoted_module(args: ModuleArgs, mut module: ItemMod) -> Result<TokenStream, Error> {
+    let path = match args.path {
+        Some(path) => path,
+        None => {
+            return Err(Error::new_spanned(
+                &module.ident,
+                "`quoted_module` requires `path = \"...\"`, the path of this module",
+            ))
+        }
+    };
+
+    expand_module(&mut module, &path)?;
+
+    Ok(quote::quote! {#module})
+}
+
+fn expand_module(module: &mut ItemMod, path: &Path) -> Result<(), Error> {
+    let items = match &mut module.content {
+        Some((_, items)) => items,
+        None => {
+            return Err(Error::new_spanned(
+                module,
+                "`quoted_module` only supports inline modules",
+            ))
+        }
+    };
+
+    for item in items {
+        match item {
+            Item::Struct(item) => add_derive(&mut item.attrs, path),
+            Item::Enum(item) => add_derive(&mut item.attrs, path),
+            Item::Mod(nested) if nested.content.is_some() => {
+                let ident = &nested.ident;
+                let nested_path: Path = parse_quote! {#path::#ident};
+                expand_module(nested, &nested_path)?;
+            }
+            _ => {}
+        }
+    }
+
+    Ok(())
+}
+
+fn add_derive(attrs: &mut Vec<Attribute>, path: &Path) {
+    if !derives_quote_it(attrs) {
+        attrs.insert(0, parse_quote! {#[derive(::quote_data::QuoteIt)]});
+    }
+    if !sets_path(attrs) {
+        attrs.push(parse_quote! {#[quote(path = #path)]});
+    }
+}
+
+fn derives_quote_it(attrs: &[Attribute]) -> bool {
+    attrs.iter().filter(|attr| attr.path().is_ident("derive")).any(|attr| {
+        let mut found = false;
+        let _ = attr.parse_nested_meta(|meta| {
+            if meta.path.segments.last().is_some_and(|segment| segment.ident == "QuoteIt") {
+                found = true;
+            }
+            Ok(())
+        });
+
+        found
+    })
+}
+
+/// Whether an item overrides the module path with its own attributes, or emits no path of
+/// its own: a `target` type replaces it and a `transparent` type emits only its field.
+///
+/// `into`, `from` and `via_str` still emit the type's path, so those items keep the module's.
+fn sets_path(attrs: &[Attribute]) -> bool {
+    attrs.iter().any(|attr| {
+        if attr.path() == MOD_PATH {
+            return true;
+        }
+        let list = match &attr.meta {
+            Meta::List(list) if attr.path() == QUOTE => list,
+            _ => return false,
+        };
+
+        // The first token of every comma separated argument names it.
+        let mut at_start = true;
+        list.tokens.clone().into_iter().any(|token| {
+            let names_path = at_start
+                && matches!(&token, TokenTree::Ident(ident) if [PATH, AUTO_PATH, REMOTE, TARGET, TRANSPARENT]
+                    .iter()
+                    .any(|name| ident == *name));
+            at_start = matches!(&token, TokenTree::Punct(punct) if punct.as_char() == ',');
+
+            names_path
+        })
+    })
+}
@@ -1,7 +1,14 @@
-use super::helper::Interpolated;
-use helpers::get_wrapped_value;
+use super::attr::{ContainerAttrs, FieldAttrs};
+use super::convert::{convert_implement, convert_source, convert_tokens, via_str_implement};
+use super::template::Template;
+use super::helper::{
+    path_source, resolve_path_statement, source_where_clause, to_tokens_impls, type_source,
+    write_path_statement, Interpolated,
+};
+use helpers::{get_target_source_value, get_target_wrapped_value, get_wrapped_value, TokenizableChunkedVec};
 use proc_macro2::TokenStream;
-use syn::{Data, DeriveInput, Error, Field as SynField, Fields, Ident, Index, Type, Generics, GenericParam};
+use quote::ToTokens;
+use syn::{Data, DeriveInput, Error, Field as SynField, Fields, Ident, Index, Path, Type, Generics, GenericParam};
 use syn::punctuated::Punctuated;
 use syn::Token;
 
@@ -26,12 +33,13 @@ pub struct StructStructure {
     name: Ident,
     fields: Option<Vec<StructField>>,
     generics: Generics,
-    mod_path: Option<TokenStream>,
     struct_type: StructType,
+    attrs: ContainerAttrs,
+    template: Option<Template>,
 }
 
 impl StructStructure {
-    pub fn from_ast(input: &DeriveInput, mod_path: Option<TokenStream>) -> Result<Self, Error> {
+    pub fn from_ast(input: &DeriveInput) -> Result<Self, Error> {
         let name = input.ident.clone();
 
         let data_struct = match &input.data {
@@ -47,8 +55,9 @@ impl StructStructure {
                     .fields
                     .iter()
                     .enumerate()
-                    .map(|(index, field)| StructField::from_ast(field, index))
-                    .collect::<Result<Vec<StructField>, Error>>()?,
+                    .map(|(index, field)| StructField::from_ast(field, index, &name.to_string()))
+                    .collect::<Result<Vec<StructField>, Error>>()
+                    .and_then(parse_field_exprs)?,
             )
         };
 
@@ -59,18 +68,58 @@ impl StructStructure {
         };
 
         let generics = input.generics.clone();
+        let attrs = ContainerAttrs::from_ast(&input.attrs)?;
+        if attrs.transparent && data_struct.fields.len() != 1 {
+            return Err(Error::new_spanned(
+                &input.ident,
+                "`transparent` structs must have exactly one field",
+            ));
+        }
+        if attrs.target.is_none() {
+            if let Some(target_ty) = fields.iter().flatten().find_map(StructField::target_ty) {
+                return Err(Error::new_spanned(target_ty, "`target_ty` requires a `target` type"));
+            }
+        }
+        check_secret_fields(fields.iter().flatten(), &attrs)?;
+        let template = match &attrs.template {
+            Some(template) => Some(Template::parse(template, fields.as_deref().unwrap_or_default())?),
+            None => None,
+        };
+        if let (Some(template), Some(fields)) = (&template, &fields) {
+            if let Some((field, attr)) = template
+                .fields()
+                .into_iter()
+                .find_map(|index| Some((&fields[index], fields[index].secret_attr()?)))
+            {
+                return Err(field.error(format!("`{}` fields can't be used in a `template`", attr)));
+            }
+        }
 
         Ok(StructStructure {
             name,
             fields,
             generics,
-            mod_path,
             struct_type,
+            attrs,
+            template,
         })
     }
 
     pub fn get_implement(self) -> Result<TokenStream, Error> {
+        if let Some(remote) = &self.attrs.remote {
+            return self.remote_implement(remote);
+        }
+
         let name = &self.name;
+        if let Some(convert) = &self.attrs.convert {
+            let type_path = self.attrs.type_path(name);
+            return convert_implement(name, &self.generics, convert, &type_path);
+        }
+        if let Some(checked) = self.attrs.via_str {
+            let type_path = self.attrs.type_path(name);
+            return Ok(via_str_implement(name, &self.generics, checked, &type_path));
+        }
+
         let (field_idents, fn_new_params, temp_values) = match &self.fields {
             Some(fields_vec) => (
                 fields_vec
@@ -81,10 +130,11 @@ impl StructStructure {
                     .iter()
                     .map(|field| field.get_construct_param())
                     .collect::<Vec<TokenStream>>(),
-                fields_vec
-                    .iter()
-                    .map(|field| field.temp_value_token_stream())
-                    .collect::<Result<Vec<TokenStream>, Error>>()?,
+                temp_value_statements(
+                    fields_vec,
+                    &|field| field.get_temp_value_ident(),
+                    &|field| (field.get_ident(), true),
+                )?,
             ),
             _ => (Vec::new(), Vec::new(), Vec::new()),
         };
@@ -98,11 +148,10 @@ impl StructStructure {
             .map(|ident| Interpolated(ident.to_string()))
             .collect();
 
-        let mod_path_token = self
-            .mod_path
-            .as_ref()
-            .map(|path| quote::quote! {#path::})
-            .unwrap_or_default();
+        let type_path = self.attrs.type_path(name);
+        let resolve_path = resolve_path_statement(&type_path);
+        let write_path = write_path_statement(&type_path);
+        let path = Interpolated("__quote_data_path".to_string());
 
         let generics = &self.generics.params;
         let generics_without_bounds: Punctuated<GenericParam, Token![,]> = self.generics.params.clone()
@@ -124,22 +173,202 @@ impl StructStructure {
                 }
             ).collect();
         let where_clause = &self.generics.where_clause;
+        let source_where_clause = source_where_clause(&self.generics);
+        let source_fields: Vec<TokenStream> = self
+            .fields
+            .iter()
+            .flatten()
+            .enumerate()
+            .map(|(index, field)| {
+                let separator = if index != 0 {
+                    quote::quote! {out.write_str(", ")?;}
+                } else {
+                    TokenStream::new()
+                };
+                // A `target` is emitted as a struct literal, with the names of named fields.
+                let separator = match (&self.attrs.target, field.ident()) {
+                    (Some(_), Some(ident)) => {
+                        let label = format!("{}: ", ident);
+                        quote::quote! {
+                            #separator
+                            out.write_str(#label)?;
+                        }
+                    }
+                    _ => separator,
+                };
+                let write_source = field_source(self.fields.as_deref().unwrap_or_default(), index, &|field| {
+                    let value = field.get_ident();
+                    quote::quote! {&#value}
+                });
+
+                quote::quote! {
+                    #separator
+                    #write_source
+                }
+            })
+            .collect();
+
+        let fields = self.fields.as_deref().unwrap_or_default();
+        let to_tokens = if let Some(template) = &self.template {
+            let resolve_path = if template.uses_self() { resolve_path } else { TokenStream::new() };
+            let template = template.quote_template(&|index| fields[index].get_temp_value_ident(), &path.to_token_stream());
+            // Fields the template leaves out, e.g. a `redact` field, are still bound.
+            quote::quote! {
+                #(#[allow(unused_variables)] #temp_values;)*
+                #resolve_path
+
+                quote_data::share_tokens(quote::quote_spanned! {quote_data::current_span()=>
+                    #template
+                }).to_tokens(tokens);
+            }
+        } else if self.attrs.transparent {
+            quote::quote! {
+                #(#temp_values;)*
+                #(#field_idents.to_tokens(tokens);)*
+            }
+        } else if self.attrs.target.is_some() {
+            let target_params: Vec<TokenStream> = fields
+                .iter()
+                .map(|field| {
+                    let value = Interpolated(field.get_temp_value_ident().to_string());
+                    match field.ident() {
+                        Some(ident) => quote::quote! {#ident: #value},
+                        None => quote::quote! {#value},
+                    }
+                })
+                .collect();
+            let target_params = self.struct_type.get_params(quote::quote! {#(#target_params),*});
+            quote::quote! {
+                #(#temp_values;)*
+                #resolve_path
+
+                quote_data::share_tokens(quote::quote_spanned! {quote_data::current_span()=>
+                    #path #target_params
+                }).to_tokens(tokens);
+            }
+        } else {
+            quote::quote! {
+                #(#temp_values;)*
+                #resolve_path
+
+                quote_data::share_tokens(quote::quote_spanned! {quote_data::current_span()=>
+                    #path::new(#(#construct_params),*)
+                }).to_tokens(tokens);
+            }
+        };
+        let write_source = if let Some(template) = &self.template {
+            let source = template.source_template(
+                &|index| {
+                    field_source(fields, index, &|field| {
+                        let value = field.get_ident();
+                        quote::quote! {&#value}
+                    })
+                },
+                &write_path,
+            );
+            quote::quote! {
+                #source
+                Ok(())
+            }
+        } else if self.attrs.transparent {
+            quote::quote! {
+                #(#source_fields)*
+                Ok(())
+            }
+        } else if self.attrs.target.is_some() {
+            let (open, close) = match self.struct_type {
+                StructType::NoField => ("", ""),
+                StructType::Tuple => ("(", ")"),
+                StructType::Struct => (" { ", " }"),
+            };
+            quote::quote! {
+                #write_path
+                out.write_str(#open)?;
+                #(#source_fields)*
+                out.write_str(#close)
+            }
+        } else {
+            quote::quote! {
+                #write_path
+                out.write_str("::new(")?;
+                #(#source_fields)*
+                out.write_str(")")
+            }
+        };
+        let with_span = match &self.attrs.span {
+            Some(span) => quote::quote! {
+                quote_data::with_span(self.#span, || {
+                    #to_tokens
+                });
+            },
+            None => to_tokens,
+        };
+
+        let to_tokens_impls = to_tokens_impls(
+            &quote::quote! {<#generics>},
+            &quote::quote! {#name <#generics_without_bounds>},
+            &where_clause.to_token_stream(),
+            &with_span,
+        );
 
         Ok(quote::quote! {
             impl<#generics> #name <#generics_without_bounds> #where_clause {
+                #[allow(clippy::too_many_arguments)]
                 pub fn new(#(#fn_new_params),*) -> Self {
                     #name #params
                 }
             }
 
-            impl<#generics> quote::ToTokens for #name <#generics_without_bounds> #where_clause {
-                fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
-                    use quote_data::Tokenizable;
-                    #(#temp_values;)*
+            #to_tokens_impls
 
-                    (quote::quote! {
-                        #mod_path_token#name::new(#(#construct_params),*)
-                    }).to_tokens(tokens);
+            impl<#generics> quote_data::ToRustSource for #name <#generics_without_bounds> #source_where_clause {
+                fn write_source(&self, out: &mut dyn ::core::fmt::Write) -> ::core::fmt::Result {
+                    #write_source
+                }
+            }
+        })
+    }
+}
+
+impl StructStructure {
+    /// `quote_remote`, which emits the foreign type `remote` from a value of it.
+    fn remote_implement(&self, remote: &Path) -> Result<TokenStream, Error> {
+        let name = &self.name;
+        let fields = self.fields.iter().flatten();
+        let temp_values = temp_value_statements(
+            self.fields.as_deref().unwrap_or_default(),
+            &|field| field.get_temp_value_ident(),
+            &|field| (field.access(&quote::quote! {__quote_data_remote}), true),
+        )?;
+        let construct_params: Vec<TokenStream> = fields
+            .map(|field| {
+                let value = Interpolated(field.get_temp_value_ident().to_string());
+                match field.ident() {
+                    Some(ident) => quote::quote! {#ident: #value},
+                    None => quote::quote! {#value},
+                }
+            })
+            .collect();
+        let params = self
+            .struct_type
+            .get_params(quote::quote! {#(#construct_params),*});
+
+        let remote_path = path_source(remote);
+        let resolve_path = resolve_path_statement(&quote::quote! {#remote_path});
+        let path = Interpolated("__quote_data_path".to_string());
+        let (impl_generics, ty_generics, where_clause) = self.generics.split_for_impl();
+
+        Ok(quote::quote! {
+            quote_data::__to_tokens_impl! {
+                impl #impl_generics #name #ty_generics #where_clause {
+                    pub fn quote_remote(__quote_data_remote: &#remote #ty_generics) -> proc_macro2::TokenStream {
+                        #(#temp_values;)*
+                        #resolve_path
+
+                        quote_data::share_tokens(quote::quote_spanned! {quote_data::current_span()=>
+                            #path #params
+                        })
+                    }
                 }
             }
         })
@@ -151,43 +380,220 @@ pub(crate) struct StructField {
     ident: Option<Ident>,
     index: usize,
     ty: Type,
+    attrs: FieldAttrs,
+    expr: Option<Template>,
+    owner: String,
+}
+
+/// Parses the `expr` templates of `fields`, which may only refer to fields without one.
+pub(crate) fn parse_field_exprs(mut fields: Vec<StructField>) -> Result<Vec<StructField>, Error> {
+    let mut exprs = Vec::new();
+    for field in fields.iter() {
+        exprs.push(match &field.attrs.expr {
+            Some(expr) => {
+                let template = Template::parse(expr, &fields)?;
+                if template.uses_self() {
+                    return Err(Error::new_spanned(expr, "`#Self` can't be used in a field `expr`"));
+                }
+                if let Some(index) = template.fields().into_iter().find(|index| fields[*index].attrs.expr.is_some()) {
+                    return Err(Error::new_spanned(
+                        expr,
+                        format!("`expr` can't refer to `{}`, which has an `expr` itself", fields[index].name()),
+                    ));
+                }
+                Some(template)
+            }
+            None => None,
+        });
+    }
+    for (field, expr) in fields.iter_mut().zip(exprs) {
+        field.expr = expr;
+    }
+
+    Ok(fields)
+}
+
+/// `let` statements binding the tokens of every field to the name `temp_ident` gives it, with
+/// the field's name in the path of `QuoteError`s.
+/// `value` gives the field's value and whether it's a place rather than a reference. Fields with
+/// an `expr` come last, so their expressions can interpolate the other fields.
+pub(crate) fn temp_value_statements(
+    fields: &[StructField],
+    temp_ident: &dyn Fn(&StructField) -> Ident,
+    value: &dyn Fn(&StructField) -> (TokenStream, bool),
+) -> Result<Vec<TokenStream>, Error> {
+    let (exprs, values): (Vec<&StructField>, Vec<&StructField>) =
+        fields.iter().partition(|field| field.expr.is_some());
+    let mut statements = Vec::new();
+
+    for field in values {
+        let ident = temp_ident(field);
+        let (value_path, as_ref) = value(field);
+        let value = field.wrapped_value(value_path, as_ref)?;
+        let name = field.name();
+        statements.push(quote::quote! {
+            let #ident = quote_data::in_field(#name, || quote::ToTokens::into_token_stream(#value))
+        });
+    }
+    for field in exprs {
+        let ident = temp_ident(field);
+        let template = field
+            .expr
+            .as_ref()
+            .map(|expr| expr.quote_template(&|index| temp_ident(&fields[index]), &TokenStream::new()));
+        let name = field.name();
+        statements.push(quote::quote! {
+            let #ident = quote_data::in_field(#name, || quote::quote_spanned! {quote_data::current_span()=>
+                #template
+            })
+        });
+    }
+
+    Ok(statements)
+}
+
+/// Statement writing the source of the field with the given index, with `value` giving the
+/// reference to a field.
+pub(crate) fn field_source(
+    fields: &[StructField],
+    index: usize,
+    value: &dyn Fn(&StructField) -> TokenStream,
+) -> TokenStream {
+    let field = &fields[index];
+    match &field.expr {
+        Some(expr) => expr.source_template(
+            &|index| fields[index].source_token_stream(value(&fields[index])),
+            &TokenStream::new(),
+        ),
+        None => field.source_token_stream(value(field)),
+    }
+}
+
+enum EnvType {
+    String,
+    Option,
+}
+
+/// Rejects `env` and `redact` fields under a container `into`, `from` or `via_str`, which quote
+/// the value as a whole and would leak the fields.
+pub fn check_secret_fields<'a>(
+    fields: impl IntoIterator<Item = &'a StructField>,
+    attrs: &ContainerAttrs,
+) -> Result<(), Error> {
+    let container = match (&attrs.convert, attrs.via_str) {
+        (Some(convert), _) if convert.from => "from",
+        (Some(_), _) => "into",
+        (None, Some(_)) => "via_str",
+        (None, None) => return Ok(()),
+    };
+
+    match fields.into_iter().find_map(|field| Some((field, field.secret_attr()?))) {
+        Some((field, attr)) => Err(field.error(format!(
+            "`{}` fields can't be combined with `{}` on the container, which quotes the whole value",
+            attr, container
+        ))),
+        None => Ok(()),
+    }
+}
+
+/// Which `env` field `ty` is, if it's a `String` or an `Option<String>`.
+fn env_type(ty: &Type) -> Option<EnvType> {
+    fn last_segment(ty: &Type) -> Option<&syn::PathSegment> {
+        match ty {
+            Type::Path(type_path) => type_path.path.segments.last(),
+            _ => None,
+        }
+    }
+
+    let segment = last_segment(ty)?;
+    if segment.ident == "String" {
+        return Some(EnvType::String);
+    }
+    match &segment.arguments {
+        syn::PathArguments::AngleBracketed(arguments) if segment.ident == "Option" => {
+            match arguments.args.first() {
+                Some(syn::GenericArgument::Type(inner))
+                    if last_segment(inner).is_some_and(|inner| inner.ident == "String") =>
+                {
+                    Some(EnvType::Option)
+                }
+                _ => None,
+            }
+        }
+        _ => None,
+    }
 }
 
 impl StructField {
-    pub fn from_ast(field: &SynField, index: usize) -> Result<Self, Error> {
+    /// `owner` is the struct or enum variant the field belongs to, e.g. `Event::Tick`.
+    pub fn from_ast(field: &SynField, index: usize, owner: &str) -> Result<Self, Error> {
         let name = field.ident.clone();
         let ty = field.ty.clone();
+        let attrs = FieldAttrs::from_ast(&field.attrs)?;
+        if attrs.env.is_some() && env_type(&ty).is_none() {
+            return Err(Error::new_spanned(&ty, "`env` is only supported on `String` and `Option<String>` fields"));
+        }
+        if let Some(target_ty) = &attrs.target_ty {
+            get_target_wrapped_value(&ty, target_ty, quote::quote! {value}, false)?;
+        }
 
         Ok(StructField {
             ident: name,
             index,
             ty,
+            attrs,
+            expr: None,
+            owner: owner.to_string(),
         })
     }
 
+    /// The attribute keeping the field's value out of the output, `env` or `redact`.
+    pub fn secret_attr(&self) -> Option<&'static str> {
+        if self.attrs.env.is_some() {
+            Some("env")
+        } else if self.attrs.redact.is_some() {
+            Some("redact")
+        } else {
+            None
+        }
+    }
+
+    /// An error pointing at the field.
+    pub fn error(&self, message: String) -> Error {
+        match &self.ident {
+            Some(ident) => Error::new_spanned(ident, message),
+            None => Error::new_spanned(&self.ty, message),
+        }
+    }
+
+    pub fn target_ty(&self) -> Option<&Type> {
+        self.attrs.target_ty.as_ref()
+    }
+
     pub fn name(&self) -> String {
         self.ident.clone()
             .map(|ident| ident.to_string())
             .unwrap_or_else(|| self.index.to_string())
     }
 
-    pub fn ty(&self) -> &Type {
-        &self.ty
-    }
-
     pub fn ident(&self) -> Option<Ident> {
         self.ident.clone()
     }
 
     fn get_ident(&self) -> TokenStream {
+        self.access(&quote::quote! {self})
+    }
+
+    /// Place of this field in the struct value `receiver`.
+    fn access(&self, receiver: &TokenStream) -> TokenStream {
         if let Some(ident) = &self.ident {
             quote::quote! {
-                self.#ident
+                #receiver.#ident
             }
         } else {
             let index = Index::from(self.index);
             quote::quote! {
-                self.#index
+                #receiver.#index
             }
         }
     }
@@ -209,11 +615,188 @@ impl StructField {
         }
     }
 
-    pub fn temp_value_token_stream(&self) -> Result<TokenStream, Error> {
-        let temp_value_ident = self.get_temp_value_ident();
-        let value = get_wrapped_value(&self.ty, self.get_ident(), true, false)?;
-        Ok(quote::quote! {
-            let #temp_value_ident = #value
-        })
+    /// Wrapper for fields that bypass the type handlers.
+    fn verbatim_wrapper(&self) -> Option<Ident> {
+        if self.attrs.reify {
+            Some(quote::format_ident!("TokenizableReified"))
+        } else if self.attrs.raw {
+            Some(quote::format_ident!("TokenizableRaw"))
+        } else {
+            None
+        }
+    }
+
+    /// Expression building the tokenizable value of this field from `value_path`.
+    pub fn wrapped_value(&self, value_path: TokenStream, as_ref: bool) -> Result<TokenStream, Error> {
+        if let Some((tokens, _)) = self.fixed_value() {
+            return Ok(quote::quote! {
+                quote::quote_spanned! {quote_data::current_span()=> #tokens}
+            });
+        }
+        if let Some(fn_path) = &self.attrs.fn_path {
+            let fn_path = path_source(fn_path);
+            return Ok(quote::quote! {
+                quote_data::QuoteContext::with(|context| context.resolve_path(#fn_path))
+            });
+        }
+        if let Some(wrapper) = self.verbatim_wrapper() {
+            let value_ref = if as_ref { quote::quote! {&#value_path} } else { value_path };
+            return Ok(quote::quote! {
+                quote_data::#wrapper::new(#value_ref)
+            });
+        }
+        if let Some(with) = &self.attrs.with {
+            let value_ref = if as_ref { quote::quote! {&#value_path} } else { value_path };
+            return Ok(quote::quote! {
+                quote_data::TokenizableWith::new(#value_ref, #with)
+            });
+        }
+        if let Some(convert) = &self.attrs.convert {
+            let value_ref = if as_ref { quote::quote! {&#value_path} } else { value_path };
+            let ty = &self.ty;
+            return convert_tokens(convert, &value_ref, &TokenStream::new(), &quote::quote! {#ty});
+        }
+        if self.attrs.via_str.is_some() {
+            let value_ref = if as_ref { quote::quote! {&#value_path} } else { value_path };
+            return Ok(self.via_str_value(&value_ref));
+        }
+        if let Some(target_ty) = &self.attrs.target_ty {
+            return get_target_wrapped_value(&self.ty, target_ty, value_path, as_ref);
+        }
+
+        match self.attrs.chunk {
+            Some(chunk_size) => TokenizableChunkedVec::<String, TokenStream>::convert_token_stream(
+                &self.ty,
+                &if as_ref { quote::quote! {&#value_path} } else { value_path },
+                chunk_size,
+                self.attrs.chunk_ty.as_ref(),
+            ),
+            None => get_wrapped_value(&self.ty, value_path, as_ref)
+        }
+    }
+
+    /// Statement writing the source of the field behind the reference `value`, with the field
+    /// appended to the path of failures.
+    pub fn source_token_stream(&self, value: TokenStream) -> TokenStream {
+        let name = self.name();
+        let statement = self.write_statement(value);
+
+        quote::quote! {
+            quote_data::in_field(#name, || -> ::core::fmt::Result {
+                #statement
+                Ok(())
+            })?;
+        }
+    }
+
+    fn write_statement(&self, value: TokenStream) -> TokenStream {
+        if let Some((_, source)) = self.fixed_value() {
+            return quote::quote! {
+                out.write_str(#source)?;
+            };
+        }
+        if let Some(fn_path) = &self.attrs.fn_path {
+            let fn_path = path_source(fn_path);
+            return write_path_statement(&quote::quote! {#fn_path});
+        }
+        if let Some(wrapper) = self.verbatim_wrapper() {
+            return quote::quote! {
+                quote_data::ToRustSource::write_source(&quote_data::#wrapper::new(#value), out)?;
+            };
+        }
+        if let Some(with) = &self.attrs.with {
+            return quote::quote! {
+                quote_data::ToRustSource::write_source(&quote_data::TokenizableWith::new(#value, #with), out)?;
+            };
+        }
+        if let Some(convert) = &self.attrs.convert {
+            let ty = type_source(&self.ty);
+            return convert_source(convert, &value, &quote::quote! {out.write_str(#ty)?;});
+        }
+        if self.attrs.via_str.is_some() {
+            let value = self.via_str_value(&value);
+            return quote::quote! {
+                quote_data::ToRustSource::write_source(&#value, out)?;
+            };
+        }
+        if let Some(target_ty) = &self.attrs.target_ty {
+            let value = get_target_source_value(&self.ty, target_ty, value, false)
+                .expect("`target_ty` is checked in `from_ast`");
+            return quote::quote! {
+                quote_data::ToRustSource::write_source(&#value, out)?;
+            };
+        }
+
+        match self.attrs.chunk {
+            Some(chunk_size) => {
+                let element_type = match &self.attrs.chunk_ty {
+                    Some(ty) => {
+                        let ty = type_source(ty);
+                        quote::quote! {Some(#ty)}
+                    }
+                    None => quote::quote! {None}
+                };
+
+                quote::quote! {
+                    quote_data::write_chunked_source(#value, #chunk_size, #element_type, out)?;
+                }
+            }
+            None => quote::quote! {
+                quote_data::ToRustSource::write_source(#value, out)?;
+            }
+        }
+    }
+
+    /// `TokenizableViaStr` of the field behind the reference `value`.
+    fn via_str_value(&self, value: &TokenStream) -> TokenStream {
+        let ty = type_source(&self.ty);
+
+        if self.attrs.via_str == Some(true) {
+            let path = format!("{}.{}", self.owner, self.name());
+            quote::quote! {
+                quote_data::TokenizableViaStr::checked(#value, #ty, #path)
+            }
+        } else {
+            quote::quote! {
+                quote_data::TokenizableViaStr::new(#value, #ty)
+            }
+        }
+    }
+
+    /// Tokens and source emitted in place of an `env` or `redact` field, which are the same
+    /// for every value.
+    fn fixed_value(&self) -> Option<(TokenStream, String)> {
+        if let Some(env) = &self.attrs.env {
+            return Some(match env_type(&self.ty) {
+                Some(EnvType::Option) => (
+                    quote::quote! {::core::option_env!(#env).map(::std::string::String::from)},
+                    format!("::core::option_env!({:?}).map(::std::string::String::from)", env.value()),
+                ),
+                _ => (
+                    quote::quote! {::core::env!(#env).to_string()},
+                    format!("::core::env!({:?}).to_string()", env.value()),
+                ),
+            });
+        }
+
+        match self.attrs.redact? {
+            true => Some((
+                quote::quote! {::core::default::Default::default()},
+                "::core::default::Default::default()".to_string(),
+            )),
+            false => {
+                let message = format!("`{}.{}` is redacted and can't be quoted", self.owner, self.name());
+                Some((
+                    quote::quote! {::core::compile_error!(#message)},
+                    format!("::core::compile_error!({:?})", message),
+                ))
+            }
+        }
+    }
+
+    /// Whether the field's value is never read, because it's emitted as its `expr`, an
+    /// environment variable, a redaction or its `fn_path`.
+    pub fn ignores_value(&self) -> bool {
+        self.expr.is_some() || self.fixed_value().is_some() || self.attrs.fn_path.is_some()
     }
 }
@@ -0,0 +1,200 @@
+use crate::r#struct::StructField;
+use proc_macro2::{Delimiter, Group, Ident, TokenStream, TokenTree};
+use quote::TokenStreamExt;
+use syn::{Error, LitStr};
+
+/// Part of a `#[quote(template = "...")]` template.
+enum Piece {
+    /// Tokens emitted as they are.
+    Tokens(TokenStream),
+    /// `#field` or `#0`, the quoted value of the field with this index.
+    Field(usize),
+    /// `#Self`, the path of the type.
+    SelfPath,
+    Group(Delimiter, Vec<Piece>),
+}
+
+/// Token template of a struct or a field `expr`, with `#field` placeholders checked against
+/// the fields.
+pub struct Template {
+    pieces: Vec<Piece>,
+}
+
+impl Template {
+    pub fn parse(template: &LitStr, fields: &[StructField]) -> Result<Self, Error> {
+        let tokens: TokenStream = template.parse()?;
+
+        Ok(Template {
+            pieces: parse_pieces(tokens, template, fields)?,
+        })
+    }
+
+    pub fn uses_self(&self) -> bool {
+        fn uses_self(pieces: &[Piece]) -> bool {
+            pieces.iter().any(|piece| match piece {
+                Piece::SelfPath => true,
+                Piece::Group(_, pieces) => uses_self(pieces),
+                _ => false,
+            })
+        }
+
+        uses_self(&self.pieces)
+    }
+
+    /// Indices of the fields the placeholders refer to.
+    pub fn fields(&self) -> Vec<usize> {
+        fn fields(pieces: &[Piece], indices: &mut Vec<usize>) {
+            for piece in pieces {
+                match piece {
+                    Piece::Field(index) => indices.push(*index),
+                    Piece::Group(_, pieces) => fields(pieces, indices),
+                    _ => {}
+                }
+            }
+        }
+
+        let mut indices = Vec::new();
+        fields(&self.pieces, &mut indices);
+
+        indices
+    }
+
+    /// Template for `quote::quote!`, with the placeholders interpolating the fields' temp
+    /// values named by `temp_ident` and `#Self` interpolating `self_path`.
+    pub fn quote_template(&self, temp_ident: &dyn Fn(usize) -> Ident, self_path: &TokenStream) -> TokenStream {
+        fn quote_pieces(
+            pieces: &[Piece],
+            temp_ident: &dyn Fn(usize) -> Ident,
+            self_path: &TokenStream,
+        ) -> TokenStream {
+            let mut tokens = TokenStream::new();
+            for piece in pieces {
+                match piece {
+                    Piece::Tokens(value) => tokens.extend(value.clone()),
+                    Piece::Field(index) => {
+                        let ident = temp_ident(*index);
+                        tokens.extend(quote::quote! {##ident});
+                    }
+                    Piece::SelfPath => tokens.extend(self_path.clone()),
+                    Piece::Group(delimiter, pieces) => tokens.append(Group::new(
+                        *delimiter,
+                        quote_pieces(pieces, temp_ident, self_path),
+                    )),
+                }
+            }
+
+            tokens
+        }
+
+        quote_pieces(&self.pieces, temp_ident, self_path)
+    }
+
+    /// Statements writing the source of the template, with `write_field` writing the field with
+    /// the given index and `write_self` the path of the type.
+    pub fn source_template(
+        &self,
+        write_field: &dyn Fn(usize) -> TokenStream,
+        write_self: &TokenStream,
+    ) -> TokenStream {
+        fn source_pieces(
+            pieces: &[Piece],
+            write_field: &dyn Fn(usize) -> TokenStream,
+            write_self: &TokenStream,
+        ) -> TokenStream {
+            let mut statements = TokenStream::new();
+            for piece in pieces {
+                statements.extend(match piece {
+                    Piece::Tokens(value) => {
+                        let text = format!("{} ", value);
+                        quote::quote! {out.write_str(#text)?;}
+                    }
+                    Piece::Field(index) => write_field(*index),
+                    Piece::SelfPath => write_self.clone(),
+                    Piece::Group(delimiter, pieces) => {
+                        let (open, close) = match delimiter {
+                            Delimiter::Parenthesis => ("(", ")"),
+                            Delimiter::Brace => ("{ ", " }"),
+                            Delimiter::Bracket => ("[", "]"),
+                            Delimiter::None => ("", ""),
+                        };
+                        let inner = source_pieces(pieces, write_field, write_self);
+                        quote::quote! {
+                            out.write_str(#open)?;
+                            #inner
+                            out.write_str(#close)?;
+                        }
+                    }
+                });
+            }
+
+            statements
+        }
+
+        source_pieces(&self.pieces, write_field, write_self)
+    }
+}
+
+fn parse_pieces(tokens: TokenStream, template: &LitStr, fields: &[StructField]) -> Result<Vec<Piece>, Error> {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut pieces = Vec::new();
+    let mut run = TokenStream::new();
+    let mut index = 0;
+
+    while index < tokens.len() {
+        let placeholder = match (&tokens[index], tokens.get(index + 1)) {
+            (TokenTree::Punct(punct), Some(TokenTree::Ident(name))) if punct.as_char() == '#' => {
+                Some(placeholder(&name.to_string(), template, fields)?)
+            }
+            (TokenTree::Punct(punct), Some(TokenTree::Literal(literal))) if punct.as_char() == '#' => {
+                Some(placeholder(&literal.to_string(), template, fields)?)
+            }
+            _ => None,
+        };
+
+        if let Some(piece) = placeholder {
+            if !run.is_empty() {
+                pieces.push(Piece::Tokens(std::mem::take(&mut run)));
+            }
+            pieces.push(piece);
+            index += 2;
+            continue;
+        }
+
+        match &tokens[index] {
+            TokenTree::Group(group) => {
+                let inner = parse_pieces(group.stream(), template, fields)?;
+                match inner.as_slice() {
+                    [] | [Piece::Tokens(_)] => run.append(group.clone()),
+                    _ => {
+                        if !run.is_empty() {
+                            pieces.push(Piece::Tokens(std::mem::take(&mut run)));
+                        }
+                        pieces.push(Piece::Group(group.delimiter(), inner));
+                    }
+                }
+            }
+            token => run.append(token.clone()),
+        }
+        index += 1;
+    }
+
+    if !run.is_empty() {
+        pieces.push(Piece::Tokens(run));
+    }
+
+    Ok(pieces)
+}
+
+/// The piece of the placeholder `#name`, which must be `Self` or name a field.
+fn placeholder(name: &str, template: &LitStr, fields: &[StructField]) -> Result<Piece, Error> {
+    if name == "Self" {
+        return Ok(Piece::SelfPath);
+    }
+
+    fields
+        .iter()
+        .position(|field| field.name() == name)
+        .map(Piece::Field)
+        .ok_or_else(|| Error::new_spanned(template, format!("unknown field `{}` in template", name)))
+}
+
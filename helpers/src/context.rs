@@ -0,0 +1,221 @@
+#[cfg(feature = "to-token")]
+use proc_macro2::{Span, TokenStream};
+#[cfg(feature = "to-token")]
+use quote::ToTokens;
+use std::cell::RefCell;
+#[cfg(feature = "to-token")]
+use std::str::FromStr;
+#[cfg(feature = "to-token")]
+use crate::span::{respan, with_span};
+use crate::try_quote::QuoteError;
+
+thread_local! {
+    static QUOTE_CONTEXT: RefCell<QuoteContext> = RefCell::new(QuoteContext::default());
+}
+
+/// How integer and float literals are emitted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum LiteralPolicy {
+    /// `1i32`, `1.5f64`: the literal keeps the type of the quoted value.
+    #[default]
+    Suffixed,
+    /// `1`, `1.5`: the type is inferred where the literal is used.
+    Unsuffixed,
+}
+
+/// How NaN and infinite floats are emitted.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum FloatPolicy {
+    /// `::core::f64::NAN`, `::core::f64::INFINITY` or `::core::f64::NEG_INFINITY`.
+    #[default]
+    Constants,
+    /// Non-finite floats are reported as a `QuoteError`.
+    Strict,
+}
+
+/// Which span emitted tokens get when no span is set explicitly.
+#[derive(Clone, Copy, Debug, Default, PartialEq, Eq)]
+pub enum Hygiene {
+    #[default]
+    CallSite,
+    MixedSite,
+}
+
+/// Options applied while tokenizing, chosen at runtime instead of with derive attributes.
+///
+/// The derived impls of nested values all read the context installed for the current thread
+/// with [`QuoteContext::scope`] or [`ToTokensWith::to_tokens_with`], so it is passed down
+/// through every field. Plain `ToTokens` uses the default context, which changes nothing.
+#[derive(Clone, Debug, Default)]
+pub struct QuoteContext {
+    paths: Vec<(String, String)>,
+    #[cfg(feature = "to-token")]
+    span: Option<Span>,
+    literals: LiteralPolicy,
+    floats: FloatPolicy,
+    hygiene: Hygiene,
+}
+
+impl QuoteContext {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Emits type paths starting with the segments `from` with `to` in their place, e.g.
+    /// `crate::model` as `my_models::model`. The longest matching prefix wins.
+    pub fn path(mut self, from: &str, to: &str) -> Self {
+        self.paths.push((normalize_path(from), normalize_path(to)));
+        self
+    }
+
+    #[cfg(feature = "to-token")]
+    pub fn span(mut self, span: Span) -> Self {
+        self.span = Some(span);
+        self
+    }
+
+    pub fn literals(mut self, literals: LiteralPolicy) -> Self {
+        self.literals = literals;
+        self
+    }
+
+    pub fn floats(mut self, floats: FloatPolicy) -> Self {
+        self.floats = floats;
+        self
+    }
+
+    pub fn hygiene(mut self, hygiene: Hygiene) -> Self {
+        self.hygiene = hygiene;
+        self
+    }
+
+    pub fn literal_policy(&self) -> LiteralPolicy {
+        self.literals
+    }
+
+    pub fn float_policy(&self) -> FloatPolicy {
+        self.floats
+    }
+
+    /// Applies the path rewrites to `path`, a path written without whitespace.
+    pub fn rewrite_path(&self, path: &str) -> String {
+        let rewrite = self
+            .paths
+            .iter()
+            .filter(|(from, _)| {
+                path == from || path.strip_prefix(from.as_str()).is_some_and(|rest| rest.starts_with("::"))
+            })
+            .max_by_key(|(from, _)| from.len());
+
+        match rewrite {
+            Some((from, to)) if to.is_empty() => path[from.len()..].trim_start_matches("::").to_string(),
+            Some((from, to)) => format!("{}{}", to, &path[from.len()..]),
+            None => path.to_string(),
+        }
+    }
+
+    /// Tokens of `path` after the path rewrites, with the current span.
+    #[cfg(feature = "to-token")]
+    pub fn resolve_path(&self, path: &str) -> TokenStream {
+        let path = self.rewrite_path(path);
+        let tokens = TokenStream::from_str(&path)
+            .unwrap_or_else(|e| panic!("invalid path `{}`: {}", path, e));
+
+        respan(tokens, crate::span::current_span())
+    }
+
+    /// Runs `f` with this context installed for the current thread.
+    pub fn scope<R>(self, f: impl FnOnce() -> R) -> R {
+        #[cfg(feature = "to-token")]
+        let span = match (self.span, self.hygiene) {
+            (Some(span), _) => span,
+            (None, Hygiene::CallSite) => Span::call_site(),
+            (None, Hygiene::MixedSite) => Span::mixed_site(),
+        };
+        let _guard = ContextGuard(Some(QUOTE_CONTEXT.with(|context| context.replace(self))));
+        #[cfg(feature = "to-token")]
+        return with_span(span, f);
+        #[cfg(not(feature = "to-token"))]
+        f()
+    }
+
+    pub fn current() -> Self {
+        QUOTE_CONTEXT.with(|context| context.borrow().clone())
+    }
+
+    /// Reads the current context without cloning it.
+    pub fn with<R>(f: impl FnOnce(&QuoteContext) -> R) -> R {
+        QUOTE_CONTEXT.with(|context| f(&context.borrow()))
+    }
+}
+
+/// Restores the previous context when dropped, even if the scope panics.
+struct ContextGuard(Option<QuoteContext>);
+
+impl Drop for ContextGuard {
+    fn drop(&mut self) {
+        if let Some(previous) = self.0.take() {
+            QUOTE_CONTEXT.with(|context| context.replace(previous));
+        }
+    }
+}
+
+/// Path of the type `name` declared in the module `module_path`, a `module_path!()`, with the
+/// leading crate name replaced by `crate_name`.
+pub fn auto_path(module_path: &str, crate_name: &str, name: &str) -> String {
+    match module_path.split_once("::") {
+        Some((_, modules)) => format!("{}::{}::{}", crate_name, modules, name),
+        None => format!("{}::{}", crate_name, name),
+    }
+}
+
+fn normalize_path(path: &str) -> String {
+    path.chars()
+        .filter(|c| !c.is_whitespace())
+        .collect::<String>()
+        .trim_end_matches("::")
+        .to_string()
+}
+
+/// Name of the `core` constant of a NaN or infinite float, which has no literal.
+pub(crate) fn non_finite_constant(value: f64) -> Option<&'static str> {
+    if value.is_nan() {
+        Some("NAN")
+    } else if value.is_infinite() && value.is_sign_positive() {
+        Some("INFINITY")
+    } else if value.is_infinite() {
+        Some("NEG_INFINITY")
+    } else {
+        None
+    }
+}
+
+/// Checks that the non-finite float `ty::constant` may be quoted under the current
+/// [`FloatPolicy`].
+pub(crate) fn non_finite_allowed(ty: &str, constant: &str) -> Result<(), QuoteError> {
+    match QuoteContext::with(QuoteContext::float_policy) {
+        FloatPolicy::Strict => Err(QuoteError::new(format!(
+            "`{}::{}` can't be quoted under `FloatPolicy::Strict`",
+            ty, constant
+        ))),
+        FloatPolicy::Constants => Ok(()),
+    }
+}
+
+/// Tokenizes a value with a [`QuoteContext`] passed down to every nested field.
+#[cfg(feature = "to-token")]
+pub trait ToTokensWith: ToTokens {
+    fn to_tokens_with(&self, context: &QuoteContext, tokens: &mut TokenStream) {
+        context.clone().scope(|| self.to_tokens(tokens))
+    }
+
+    fn to_token_stream_with(&self, context: &QuoteContext) -> TokenStream {
+        let mut tokens = TokenStream::new();
+        self.to_tokens_with(context, &mut tokens);
+
+        tokens
+    }
+}
+
+#[cfg(feature = "to-token")]
+impl<T: ToTokens + ?Sized> ToTokensWith for T {}
@@ -0,0 +1,45 @@
+#[cfg(feature = "to-token")]
+use proc_macro2::TokenStream;
+#[cfg(feature = "to-token")]
+use quote::ToTokens;
+use std::fmt::{self, Write as FmtWrite};
+use crate::source::ToRustSource;
+
+/// Object-safe form of `ToTokens` and `ToRustSource`, implemented by every type with both.
+///
+/// `ToTokens` can't be used through a trait object, so a trait whose objects are quoted takes
+/// `DynQuote` as a supertrait, and [`dyn_quote!`](crate::dyn_quote) implements `ToTokens` and
+/// `ToRustSource` for the trait object type with it:
+///
+/// ```ignore
+/// trait Rule: quote_data::DynQuote {}
+/// quote_data::dyn_quote!(dyn Rule);
+/// ```
+///
+/// `Box<dyn Rule>` is then emitted as `Box::new(..)` of the concrete value.
+///
+/// Without the `to-token` feature only the `ToRustSource` half exists.
+pub trait DynQuote {
+    #[cfg(feature = "to-token")]
+    fn dyn_to_tokens(&self, tokens: &mut TokenStream);
+
+    fn dyn_write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result;
+}
+
+#[cfg(feature = "to-token")]
+impl<T: ToTokens + ToRustSource> DynQuote for T {
+    fn dyn_to_tokens(&self, tokens: &mut TokenStream) {
+        self.to_tokens(tokens)
+    }
+
+    fn dyn_write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        self.write_source(out)
+    }
+}
+
+#[cfg(not(feature = "to-token"))]
+impl<T: ToRustSource> DynQuote for T {
+    fn dyn_write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        self.write_source(out)
+    }
+}
@@ -0,0 +1,164 @@
+use std::cell::RefCell;
+use std::env;
+use std::fmt::{self, Display};
+use std::fs;
+use std::path::{Path, PathBuf};
+
+thread_local! {
+    static EMIT_CONFIG: RefCell<EmitConfig> = RefCell::new(EmitConfig::default());
+}
+
+/// Options for how large values are emitted.
+///
+/// `ToTokens::to_tokens` takes no options, so the config is installed for the current thread
+/// with [`EmitConfig::scope`] and read by the handlers while tokenizing.
+#[derive(Clone, Debug, Default)]
+pub struct EmitConfig {
+    side_file_dir: Option<PathBuf>,
+    side_file_threshold: usize,
+    chunk_size: Option<usize>,
+}
+
+impl EmitConfig {
+    pub fn new() -> Self {
+        Self::default()
+    }
+
+    /// Byte and string blobs longer than `threshold` bytes are written to `dir` and emitted as
+    /// `include_bytes!`/`include_str!`.
+    ///
+    /// Inside a build script's `OUT_DIR` the path is emitted relative to it, as
+    /// `concat!(env!("OUT_DIR"), "/...")`; otherwise `dir` is emitted as it is given.
+    pub fn side_files(mut self, dir: impl Into<PathBuf>, threshold: usize) -> Self {
+        self.side_file_dir = Some(dir.into());
+        self.side_file_threshold = threshold;
+        self
+    }
+
+    /// Collections longer than `chunk_size` are emitted in chunks of `chunk_size` elements,
+    /// as with a `#[quote(chunk = ...)]` field attribute.
+    pub fn chunks(mut self, chunk_size: usize) -> Self {
+        self.chunk_size = Some(chunk_size.max(1));
+        self
+    }
+
+    pub fn chunk_size(&self) -> Option<usize> {
+        self.chunk_size
+    }
+
+    /// Runs `f` with this config installed for the current thread.
+    pub fn scope<R>(self, f: impl FnOnce() -> R) -> R {
+        let _guard = ConfigGuard(Some(EMIT_CONFIG.with(|config| config.replace(self))));
+        f()
+    }
+
+    pub fn current() -> Self {
+        EMIT_CONFIG.with(|config| config.borrow().clone())
+    }
+}
+
+/// Restores the previous config when dropped, even if the scope panics.
+struct ConfigGuard(Option<EmitConfig>);
+
+impl Drop for ConfigGuard {
+    fn drop(&mut self) {
+        if let Some(previous) = self.0.take() {
+            EMIT_CONFIG.with(|config| config.replace(previous));
+        }
+    }
+}
+
+/// Path of a side file, emitted as the argument of `include_bytes!`/`include_str!`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct SideFile {
+    path: String,
+    in_out_dir: bool,
+}
+
+impl Display for SideFile {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        if self.in_out_dir {
+            write!(f, "concat!(env!(\"OUT_DIR\"), {:?})", self.path)
+        } else {
+            write!(f, "{:?}", self.path)
+        }
+    }
+}
+
+#[cfg(feature = "to-token")]
+impl quote::ToTokens for SideFile {
+    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+        let path = &self.path;
+        if self.in_out_dir {
+            tokens.extend(quote::quote! {concat!(env!("OUT_DIR"), #path)});
+        } else {
+            path.to_tokens(tokens);
+        }
+    }
+}
+
+/// Writes `bytes` to a side file if the current config asks for it and returns the file's path.
+///
+/// Files are named after a 64-bit FNV-1a hash of their content, which doesn't change between
+/// Rust releases, so emitting the same blob twice reuses one file. A different blob with the
+/// same hash gets a numbered name instead of overwriting it.
+pub fn side_file(bytes: &[u8], extension: &str) -> Option<SideFile> {
+    let config = EmitConfig::current();
+    let dir = config.side_file_dir.as_ref()?;
+    if bytes.len() <= config.side_file_threshold {
+        return None;
+    }
+
+    let hash = fnv1a(bytes);
+    let mut collisions = 0;
+    let path = loop {
+        let name = match collisions {
+            0 => format!("blob_{:016x}.{}", hash, extension),
+            n => format!("blob_{:016x}_{}.{}", hash, n, extension),
+        };
+        let path = dir.join(name);
+        match fs::read(&path) {
+            Ok(content) if content == bytes => break path,
+            Ok(_) => collisions += 1,
+            Err(_) => {
+                fs::create_dir_all(dir)
+                    .and_then(|_| fs::write(&path, bytes))
+                    .unwrap_or_else(|e| panic!("failed to write side file {}: {}", path.display(), e));
+                break path;
+            }
+        }
+    };
+
+    Some(side_file_path(&path))
+}
+
+fn fnv1a(bytes: &[u8]) -> u64 {
+    bytes.iter().fold(0xcbf2_9ce4_8422_2325, |hash, byte| {
+        (hash ^ u64::from(*byte)).wrapping_mul(0x0100_0000_01b3)
+    })
+}
+
+/// `path` relative to `OUT_DIR` when it's inside it, and as it is otherwise.
+fn side_file_path(path: &Path) -> SideFile {
+    let relative = env::var_os("OUT_DIR").and_then(|out_dir| {
+        let out_dir = fs::canonicalize(&out_dir).unwrap_or_else(|_| PathBuf::from(out_dir));
+        let path = fs::canonicalize(path).ok()?;
+        path.strip_prefix(out_dir).ok().map(|relative| {
+            relative
+                .components()
+                .map(|component| format!("/{}", component.as_os_str().to_string_lossy()))
+                .collect::<String>()
+        })
+    });
+
+    match relative {
+        Some(path) => SideFile {
+            path,
+            in_out_dir: true,
+        },
+        None => SideFile {
+            path: path.to_string_lossy().into_owned(),
+            in_out_dir: false,
+        },
+    }
+}
@@ -7,7 +7,11 @@ pub enum QuoteItError {
     #[error("{0} expected {1} generic parameters, {1} got")]
     TypeParamCountError(&'static str, usize, usize),
     #[error("Path argument must be angle bracketed args")]
-    NotAngleBracketedArgs
+    NotAngleBracketedArgs,
+    #[error("`chunk` can only be used on `Vec` fields")]
+    ChunkNotVec,
+    #[error("`target_ty` can't be produced from a `{0}` field: no conversion from `{0}` to `{1}`")]
+    TargetTypeError(String, String),
 }
 
 impl QuoteItError {
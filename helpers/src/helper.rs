@@ -29,6 +29,21 @@ pub fn get_nested_types(arguments: Option<&AngleBracketedGenericArguments>) -> V
     }
 }
 
+pub fn is_type(ty: &Type, name: &str) -> bool {
+    match ty {
+        Type::Path(type_path) => type_path.qself.is_none() && type_path.path.is_ident(name),
+        Type::Group(group) => is_type(&group.elem, name),
+        _ => false,
+    }
+}
+
+pub fn is_slice_of(ty: &Type, name: &str) -> bool {
+    match ty {
+        Type::Slice(slice) => is_type(&slice.elem, name),
+        _ => false,
+    }
+}
+
 #[derive(Clone, Debug)]
 pub struct TokenizableError;
 
@@ -1,18 +1,84 @@
+#[cfg(feature = "to-token")]
 mod helper;
+#[cfg(feature = "to-token")]
 mod tokenizable;
+#[cfg(feature = "to-token")]
 mod error;
+mod emit;
+#[cfg(feature = "to-token")]
+mod share;
+mod pointer;
+mod share_source;
+mod source;
+#[cfg(feature = "to-token")]
+mod reify;
+#[cfg(feature = "to-token")]
+mod splice;
+#[cfg(feature = "to-token")]
+mod span;
+mod context;
+mod via_str;
+mod named_fn;
+mod dyn_quote;
+mod try_quote;
+mod target;
+#[cfg(feature = "to-token")]
+pub mod render;
 
-pub use helper::TokenizableError;
+pub use emit::{side_file, EmitConfig, SideFile};
+pub use pointer::{enter_pointer, PointerGuard, SharedValueError};
+pub use context::{auto_path, FloatPolicy, Hygiene, LiteralPolicy, QuoteContext};
+pub use via_str::TokenizableViaStr;
+pub use named_fn::NamedFn;
+pub use dyn_quote::DynQuote;
+pub use try_quote::{fail_source, in_field, in_index, try_write_source, QuoteError};
+pub use share_source::shared_rust_source;
+pub use target::{TargetBox, TargetOption, TargetSlice, TargetVec};
+pub use source::{write_chunked_source, write_rust_source, write_source_list, ToRustSource};
 
-use proc_macro2::TokenStream;
-use syn::{Error, Type};
-pub use tokenizable::*;
+#[cfg(feature = "to-token")]
+pub use to_token::*;
 
-pub fn get_wrapped_value(
-    ty: &Type,
-    value_path: TokenStream,
-    as_ref: bool,
-    clone: bool,
-) -> Result<TokenStream, Error> {
-    get_value_wrapper(ty, value_path, as_ref, clone)
+/// Everything that builds `TokenStream`s, which only exists with the `to-token` feature.
+#[cfg(feature = "to-token")]
+mod to_token {
+    pub use crate::helper::TokenizableError;
+    pub use crate::share::{quote_shared, share_pointer, share_tokens};
+    pub use crate::tokenizable::*;
+    pub use crate::reify::{ReifyTokens, Reified, TokenizableReified};
+    pub use crate::splice::{fill_holes, Splice, TokenizableRaw, TokenizableWith};
+    pub use crate::context::ToTokensWith;
+    pub use crate::span::{current_span, respan, with_span, ToTokensSpanned};
+    pub use crate::try_quote::{fail, quote_or_fail, try_quote, TryQuote};
+
+    use proc_macro2::TokenStream;
+    use syn::{Error, Type};
+
+    /// Like [`get_wrapped_value`], emitting the value as one of type `target`.
+    pub fn get_target_wrapped_value(
+        ty: &Type,
+        target: &Type,
+        value_path: TokenStream,
+        as_ref: bool,
+    ) -> Result<TokenStream, Error> {
+        get_target_wrapper(ty, target, value_path, as_ref)
+    }
+
+    /// Like [`get_target_wrapped_value`], building a `ToRustSource` value of the same source.
+    pub fn get_target_source_value(
+        ty: &Type,
+        target: &Type,
+        value_path: TokenStream,
+        as_ref: bool,
+    ) -> Result<TokenStream, Error> {
+        get_target_source(ty, target, value_path, as_ref)
+    }
+
+    pub fn get_wrapped_value(
+        ty: &Type,
+        value_path: TokenStream,
+        as_ref: bool,
+    ) -> Result<TokenStream, Error> {
+        get_value_wrapper(ty, value_path, as_ref)
+    }
 }
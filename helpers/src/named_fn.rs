@@ -0,0 +1,69 @@
+#[cfg(feature = "to-token")]
+use proc_macro2::TokenStream;
+#[cfg(feature = "to-token")]
+use quote::ToTokens;
+use std::fmt::{self, Write as FmtWrite};
+use std::ops::Deref;
+use crate::context::QuoteContext;
+use crate::source::ToRustSource;
+#[cfg(feature = "to-token")]
+use crate::span::current_span;
+
+/// A function pointer with the path of the function, so tables of callbacks can be quoted.
+///
+/// It is emitted as `quote_data::NamedFn::new(path as _, "path")`, with the `QuoteContext`
+/// path rewrites applied to the path. Build one with [`named_fn!`](crate::named_fn) or
+/// [`NamedFn::new`].
+#[derive(Clone)]
+pub struct NamedFn<F> {
+    function: F,
+    path: &'static str,
+}
+
+impl<F> NamedFn<F> {
+    /// `path` is the path of `function` as written in Rust source.
+    pub const fn new(function: F, path: &'static str) -> Self {
+        NamedFn { function, path }
+    }
+
+    pub fn function(&self) -> &F {
+        &self.function
+    }
+
+    pub fn path(&self) -> &'static str {
+        self.path
+    }
+
+    /// The path without whitespace, before the `QuoteContext` path rewrites.
+    fn path_source(&self) -> String {
+        self.path.chars().filter(|c| !c.is_whitespace()).collect()
+    }
+}
+
+impl<F> Deref for NamedFn<F> {
+    type Target = F;
+
+    fn deref(&self) -> &F {
+        &self.function
+    }
+}
+
+#[cfg(feature = "to-token")]
+impl<F> ToTokens for NamedFn<F> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let (path, text) = QuoteContext::with(|context| {
+            let path = self.path_source();
+            (context.resolve_path(&path), context.rewrite_path(&path))
+        });
+        (quote::quote_spanned! {current_span()=>
+            quote_data::NamedFn::new(#path as _, #text)
+        }).to_tokens(tokens)
+    }
+}
+
+impl<F> ToRustSource for NamedFn<F> {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        let path = QuoteContext::with(|context| context.rewrite_path(&self.path_source()));
+        write!(out, "quote_data::NamedFn::new({} as _, {:?})", path, path)
+    }
+}
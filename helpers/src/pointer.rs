@@ -0,0 +1,35 @@
+use std::cell::RefCell;
+use std::collections::HashSet;
+use thiserror::Error;
+
+thread_local! {
+    static IN_PROGRESS: RefCell<HashSet<usize>> = RefCell::new(HashSet::new());
+}
+
+#[derive(Error, Debug, Clone, PartialEq)]
+pub enum SharedValueError {
+    #[error("reference cycle through `{0}` can't be quoted")]
+    Cycle(&'static str),
+}
+
+/// Marks the allocation at `address` as being quoted until the guard is dropped.
+pub fn enter_pointer(
+    address: usize,
+    type_name: &'static str,
+) -> Result<PointerGuard, SharedValueError> {
+    IN_PROGRESS.with(|in_progress| {
+        if in_progress.borrow_mut().insert(address) {
+            Ok(PointerGuard(address))
+        } else {
+            Err(SharedValueError::Cycle(type_name))
+        }
+    })
+}
+
+pub struct PointerGuard(usize);
+
+impl Drop for PointerGuard {
+    fn drop(&mut self) {
+        IN_PROGRESS.with(|in_progress| in_progress.borrow_mut().remove(&self.0));
+    }
+}
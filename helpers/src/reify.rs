@@ -0,0 +1,203 @@
+use proc_macro2::{Ident, Literal, TokenStream, TokenTree};
+use quote::ToTokens;
+use std::fmt::{self, Write as FmtWrite};
+use std::ops::{Deref, DerefMut};
+use crate::source::ToRustSource;
+
+/// Emits an expression that rebuilds a syn or proc_macro2 value at runtime.
+///
+/// The default `ToTokens` of these types pastes their tokens into the generated code, so an
+/// `Ident` field becomes a bare identifier there. Reifying emits a constructor instead:
+/// `Ident::new(..)` for identifiers, `quote::quote! {..}` for token streams and
+/// `syn::parse_quote! {..}` for syn nodes. Tokens containing `#` can't go through `quote!`
+/// and are parsed from a string instead.
+///
+/// The generated code refers to `::proc_macro2`, `::quote` and `::syn`, so the crate using
+/// it must depend on them.
+pub trait ReifyTokens {
+    fn reify_tokens(&self) -> TokenStream;
+}
+
+fn contains_pound(tokens: TokenStream) -> bool {
+    tokens.into_iter().any(|token| match token {
+        TokenTree::Punct(punct) => punct.as_char() == '#',
+        TokenTree::Group(group) => contains_pound(group.stream()),
+        _ => false,
+    })
+}
+
+fn token_stream_from_str(tokens: &TokenStream) -> TokenStream {
+    let text = tokens.to_string();
+    quote::quote! {
+        <::proc_macro2::TokenStream as ::core::str::FromStr>::from_str(#text).unwrap()
+    }
+}
+
+impl ReifyTokens for Ident {
+    fn reify_tokens(&self) -> TokenStream {
+        let text = self.to_string();
+        match text.strip_prefix("r#") {
+            Some(raw) => quote::quote! {
+                ::proc_macro2::Ident::new_raw(#raw, ::proc_macro2::Span::call_site())
+            },
+            None => quote::quote! {
+                ::proc_macro2::Ident::new(#text, ::proc_macro2::Span::call_site())
+            },
+        }
+    }
+}
+
+impl ReifyTokens for Literal {
+    fn reify_tokens(&self) -> TokenStream {
+        let text = self.to_string();
+        quote::quote! {
+            <::proc_macro2::Literal as ::core::str::FromStr>::from_str(#text).unwrap()
+        }
+    }
+}
+
+impl ReifyTokens for TokenStream {
+    fn reify_tokens(&self) -> TokenStream {
+        if contains_pound(self.clone()) {
+            token_stream_from_str(self)
+        } else {
+            quote::quote! {
+                ::quote::quote! {#self}
+            }
+        }
+    }
+}
+
+macro_rules! reify_syn {
+    ($($ty:ty),*) => {
+        $(
+            impl ReifyTokens for $ty {
+                fn reify_tokens(&self) -> TokenStream {
+                    let tokens = self.to_token_stream();
+                    if contains_pound(tokens.clone()) {
+                        let tokens = token_stream_from_str(&tokens);
+                        quote::quote! {
+                            ::syn::parse2(#tokens).unwrap()
+                        }
+                    } else {
+                        quote::quote! {
+                            ::syn::parse_quote! {#tokens}
+                        }
+                    }
+                }
+            }
+        )*
+    };
+}
+
+reify_syn!(
+    syn::Type,
+    syn::Path,
+    syn::Expr,
+    syn::Lit,
+    syn::LitStr,
+    syn::Lifetime,
+    syn::Visibility,
+    syn::Generics,
+    syn::WhereClause,
+    syn::Attribute,
+    syn::GenericArgument,
+    syn::Member
+);
+
+impl<T: ReifyTokens + ?Sized> ReifyTokens for &T {
+    fn reify_tokens(&self) -> TokenStream {
+        (**self).reify_tokens()
+    }
+}
+
+impl<T: ReifyTokens + ?Sized> ReifyTokens for Box<T> {
+    fn reify_tokens(&self) -> TokenStream {
+        let value = (**self).reify_tokens();
+        quote::quote! {
+            ::std::boxed::Box::new(#value)
+        }
+    }
+}
+
+impl<T: ReifyTokens> ReifyTokens for Option<T> {
+    fn reify_tokens(&self) -> TokenStream {
+        match self.as_ref().map(ReifyTokens::reify_tokens) {
+            Some(value) => quote::quote! {
+                Some(#value)
+            },
+            None => quote::quote! {
+                None
+            },
+        }
+    }
+}
+
+impl<T: ReifyTokens> ReifyTokens for Vec<T> {
+    fn reify_tokens(&self) -> TokenStream {
+        let items = self.iter().map(ReifyTokens::reify_tokens);
+        quote::quote! {
+            vec![#(#items),*]
+        }
+    }
+}
+
+/// Tokenizes the value behind a `#[quote(reify)]` field.
+pub struct TokenizableReified<'a, T: ?Sized>(pub &'a T);
+
+impl<'a, T: ReifyTokens + ?Sized> TokenizableReified<'a, T> {
+    pub fn new(value: &'a T) -> Self {
+        TokenizableReified(value)
+    }
+}
+
+impl<'a, T: ReifyTokens + ?Sized> ToTokens for TokenizableReified<'a, T> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.0.reify_tokens().to_tokens(tokens)
+    }
+}
+
+impl<'a, T: ReifyTokens + ?Sized> ToRustSource for TokenizableReified<'a, T> {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        write!(out, "{}", self.0.reify_tokens())
+    }
+}
+
+/// Field type that is always reified, as if the field had `#[quote(reify)]`.
+#[derive(Clone, Debug, Default, PartialEq, Eq, Hash)]
+pub struct Reified<T>(pub T);
+
+impl<T> Deref for Reified<T> {
+    type Target = T;
+
+    fn deref(&self) -> &T {
+        &self.0
+    }
+}
+
+impl<T> DerefMut for Reified<T> {
+    fn deref_mut(&mut self) -> &mut T {
+        &mut self.0
+    }
+}
+
+impl<T> From<T> for Reified<T> {
+    fn from(value: T) -> Self {
+        Reified(value)
+    }
+}
+
+impl<T: ReifyTokens> ToTokens for Reified<T> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let value = self.0.reify_tokens();
+        (quote::quote! {
+            quote_data::Reified(#value)
+        }).to_tokens(tokens)
+    }
+}
+
+impl<T: ReifyTokens> ToRustSource for Reified<T> {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        write!(out, "quote_data::Reified({})", self.0.reify_tokens())
+    }
+}
@@ -0,0 +1,408 @@
+use proc_macro2::{Delimiter, Group, Spacing, TokenStream, TokenTree};
+
+const MAX_WIDTH: usize = 100;
+const INDENT: &str = "    ";
+
+const KEYWORDS: [&str; 28] = [
+    "as", "break", "const", "continue", "dyn", "else", "enum", "extern", "fn", "for", "if",
+    "impl", "in", "let", "loop", "match", "mod", "move", "mut", "pub", "ref", "return",
+    "static", "struct", "trait", "type", "unsafe", "where",
+];
+
+/// Render a token stream as indented Rust source.
+///
+/// Brace groups always open a new indented block, `;` ends a line, and parenthesized or
+/// bracketed lists that don't fit in 100 columns are broken into one element per line.
+/// The output only depends on the tokens, so rendering the same stream twice gives the
+/// same string.
+pub fn pretty(tokens: &TokenStream) -> String {
+    let mut printer = Printer::default();
+    printer.block_content(tokens.clone().into_iter().collect(), false);
+
+    printer.out.trim().to_string()
+}
+
+#[derive(Clone, Copy, PartialEq)]
+enum Atom {
+    Start,
+    Ident,
+    Keyword,
+    Literal,
+    Op,
+    Group(Delimiter),
+}
+
+#[derive(Default)]
+struct Printer {
+    out: String,
+    indent: usize,
+    last: Option<(Atom, String)>,
+    generic_depth: usize,
+    closure_open: bool,
+}
+
+impl Printer {
+    fn column(&self) -> usize {
+        match self.out.rfind('\n') {
+            Some(index) => self.out.len() - index - 1,
+            None => self.out.len(),
+        }
+    }
+
+    fn new_line(&mut self) {
+        let trimmed_len = self.out.trim_end_matches(' ').len();
+        self.out.truncate(trimmed_len);
+        if !self.out.is_empty() && !self.out.ends_with('\n') {
+            self.out.push('\n');
+        }
+        for _ in 0..self.indent {
+            self.out.push_str(INDENT);
+        }
+        self.last = None;
+    }
+
+    fn last_atom(&self) -> Atom {
+        self.last.as_ref().map(|(atom, _)| *atom).unwrap_or(Atom::Start)
+    }
+
+    fn last_text(&self) -> &str {
+        self.last.as_ref().map(|(_, text)| text.as_str()).unwrap_or("")
+    }
+
+    fn push(&mut self, atom: Atom, text: &str, space_before: bool) {
+        if space_before && !self.out.is_empty() && !self.out.ends_with([' ', '\n', '(', '[']) {
+            self.out.push(' ');
+        }
+        self.out.push_str(text);
+        self.last = Some((atom, text.to_string()));
+    }
+
+    fn word(&mut self, atom: Atom, text: &str) {
+        let attached = matches!(self.last_text(), "::" | "." | "&" | "!" | "#" | "'" | "$")
+            || self.is_prefix_op()
+            || (self.last_text() == "<" && self.generic_depth > 0)
+            || (self.last_text() == "|" && self.closure_open);
+        self.push(atom, text, !attached);
+    }
+
+    fn is_prefix_op(&self) -> bool {
+        let text = self.last_text();
+        if !matches!(text, "-" | "*") {
+            return false;
+        }
+        let before = self.out[..self.out.len() - text.len()].trim_end();
+
+        before.is_empty() || before.ends_with(['(', '[', '{', ',', '=', '>', '|'])
+    }
+
+    fn op(&mut self, text: &str) {
+        let last_atom = self.last_atom();
+        let last_text = self.last_text().to_string();
+        let after_value = matches!(last_atom, Atom::Ident | Atom::Literal | Atom::Group(_))
+            || (last_text == ">" && self.generic_depth == 0 && last_atom == Atom::Op);
+
+        match text {
+            "," | ";" | "." | "?" | ":" => {
+                if text == ":" && last_text == "'" {
+                    self.push(Atom::Op, text, true)
+                } else {
+                    self.push(Atom::Op, text, false)
+                }
+            }
+            "::" => {
+                let space = !(after_value || last_text == ">" || last_text == "::" || last_text == "<");
+                self.push(Atom::Op, text, space && !self.out.ends_with(['(', '[', '<', '&']))
+            }
+            "!" if last_atom == Atom::Ident => self.push(Atom::Op, text, false),
+            "<" if last_atom == Atom::Ident || matches!(last_text.as_str(), "::" | "&" | "impl") => {
+                self.generic_depth += 1;
+                self.push(Atom::Op, text, last_text == "&");
+            }
+            ">" if self.generic_depth > 0 => {
+                self.generic_depth -= 1;
+                self.push(Atom::Op, text, false)
+            }
+            ">>" if self.generic_depth > 1 => {
+                self.generic_depth -= 2;
+                self.push(Atom::Op, text, false)
+            }
+            "|" if !after_value || self.closure_open => {
+                if self.closure_open {
+                    self.closure_open = false;
+                    self.push(Atom::Op, text, false)
+                } else {
+                    self.closure_open = true;
+                    self.push(Atom::Op, text, true)
+                }
+            }
+            "&" | "-" | "*" | "!" | "#" | "'" | "$" if !after_value => {
+                let space = !(last_text == "&" || last_text == "<" && self.generic_depth > 0);
+                self.push(Atom::Op, text, space)
+            }
+            _ => self.push(Atom::Op, text, true),
+        }
+    }
+
+    fn flat(tokens: &[TokenTree]) -> String {
+        let mut printer = Printer::default();
+        printer.inline(tokens);
+
+        printer.out
+    }
+
+    fn inline(&mut self, tokens: &[TokenTree]) {
+        let mut index = 0;
+        while index < tokens.len() {
+            index = self.token(tokens, index, true);
+        }
+    }
+
+    /// Writes the token at `index` and returns the index of the next unwritten token.
+    fn token(&mut self, tokens: &[TokenTree], index: usize, inline: bool) -> usize {
+        match &tokens[index] {
+            TokenTree::Ident(ident) => {
+                let text = ident.to_string();
+                let atom = if KEYWORDS.contains(&text.as_str()) {
+                    Atom::Keyword
+                } else {
+                    Atom::Ident
+                };
+                self.word(atom, &text);
+                index + 1
+            }
+            TokenTree::Literal(literal) => {
+                self.word(Atom::Literal, &literal.to_string());
+                index + 1
+            }
+            TokenTree::Punct(punct) if punct.as_char() == '\'' => match tokens.get(index + 1) {
+                // A lifetime is one word, `'a`.
+                Some(TokenTree::Ident(ident)) => {
+                    self.word(Atom::Ident, &format!("'{}", ident));
+                    index + 2
+                }
+                _ => {
+                    self.op("'");
+                    index + 1
+                }
+            },
+            TokenTree::Punct(_) => {
+                let (text, next) = joined_punct(tokens, index);
+                self.op(&text);
+                next
+            }
+            TokenTree::Group(group) if group.delimiter() == Delimiter::None => {
+                // Invisible groups keep their precedence: `$e * 2` with `$e` being `1 + 1` is
+                // `(1 + 1) * 2`, so they're parenthesized unless they hold a single tree.
+                let nested: Vec<TokenTree> = group.stream().into_iter().collect();
+                if nested.len() == 1 {
+                    self.token(&nested, 0, inline);
+                } else if !nested.is_empty() {
+                    let group = Group::new(Delimiter::Parenthesis, group.stream());
+                    if inline {
+                        self.inline_group(&group);
+                    } else {
+                        self.group(&group);
+                    }
+                }
+                index + 1
+            }
+            TokenTree::Group(group) => {
+                if inline {
+                    self.inline_group(group);
+                } else {
+                    self.group(group);
+                }
+                index + 1
+            }
+        }
+    }
+
+    fn open_group(&mut self, delimiter: Delimiter) {
+        let last_atom = self.last_atom();
+        let last_text = self.last_text().to_string();
+        let (open, space) = match delimiter {
+            Delimiter::Parenthesis | Delimiter::Bracket => {
+                let attached = matches!(last_atom, Atom::Ident | Atom::Group(_))
+                    || last_text == "!"
+                    || last_text == "#"
+                    || (matches!(last_text.as_str(), ">" | ">>") && last_atom == Atom::Op)
+                    || (last_text == "&" && last_atom == Atom::Op)
+                    || self.is_prefix_op();
+                let open = if delimiter == Delimiter::Parenthesis { "(" } else { "[" };
+                (open, !attached)
+            }
+            Delimiter::Brace => ("{", true),
+            Delimiter::None => ("", false),
+        };
+        self.push(Atom::Op, open, space);
+        self.last = None;
+        self.generic_depth = 0;
+        self.closure_open = false;
+    }
+
+    fn close_group(&mut self, delimiter: Delimiter, state: (usize, bool)) {
+        let close = match delimiter {
+            Delimiter::Parenthesis => ")",
+            Delimiter::Bracket => "]",
+            Delimiter::Brace => "}",
+            Delimiter::None => "",
+        };
+        self.out.push_str(close);
+        self.last = Some((Atom::Group(delimiter), close.to_string()));
+        self.generic_depth = state.0;
+        self.closure_open = state.1;
+    }
+
+    fn inline_group(&mut self, group: &Group) {
+        let state = (self.generic_depth, self.closure_open);
+        let tokens: Vec<TokenTree> = group.stream().into_iter().collect();
+        self.open_group(group.delimiter());
+        if group.delimiter() == Delimiter::Brace && !tokens.is_empty() {
+            self.out.push(' ');
+            self.inline(&tokens);
+            self.out.push(' ');
+        } else {
+            self.inline(&tokens);
+        }
+        self.close_group(group.delimiter(), state);
+    }
+
+    fn group(&mut self, group: &Group) {
+        let tokens: Vec<TokenTree> = group.stream().into_iter().collect();
+        let state = (self.generic_depth, self.closure_open);
+
+        match group.delimiter() {
+            Delimiter::Brace => {
+                self.open_group(Delimiter::Brace);
+                if !tokens.is_empty() {
+                    self.indent += 1;
+                    self.new_line();
+                    self.block_content(tokens, true);
+                    self.indent -= 1;
+                    self.new_line();
+                }
+                self.close_group(Delimiter::Brace, state);
+            }
+            delimiter => {
+                let flat = Self::flat(&tokens);
+                let fits = self.column() + flat.len() + 3 <= MAX_WIDTH
+                    && !contains_statement(&tokens);
+
+                self.open_group(delimiter);
+                if fits || tokens.is_empty() {
+                    self.out.push_str(&flat);
+                } else {
+                    let elements = split_commas(&tokens);
+                    let trailing_comma =
+                        tokens.last().map(|token| is_punct(token, ',')).unwrap_or(false);
+
+                    self.indent += 1;
+                    for (index, element) in elements.iter().enumerate() {
+                        self.new_line();
+                        self.list_element(element);
+                        if index + 1 < elements.len() || trailing_comma {
+                            self.out.push(',');
+                        }
+                    }
+                    self.indent -= 1;
+                    self.new_line();
+                }
+                self.close_group(delimiter, state);
+            }
+        }
+    }
+
+    fn list_element(&mut self, tokens: &[TokenTree]) {
+        let mut index = 0;
+        while index < tokens.len() {
+            index = self.token(tokens, index, false);
+        }
+    }
+
+    fn block_content(&mut self, tokens: Vec<TokenTree>, in_brace: bool) {
+        let mut index = 0;
+        while index < tokens.len() {
+            let start = index;
+            index = self.token(&tokens, index, false);
+
+            let ends_line = match &tokens[start] {
+                TokenTree::Punct(punct) => {
+                    punct.as_char() == ';' || (in_brace && punct.as_char() == ',')
+                }
+                TokenTree::Group(group) => match group.delimiter() {
+                    Delimiter::Brace => !continues_after_block(tokens.get(index)),
+                    Delimiter::Bracket => start > 0 && is_punct(&tokens[start - 1], '#'),
+                    _ => false,
+                },
+                _ => false,
+            };
+            if ends_line && index < tokens.len() {
+                self.new_line();
+            }
+        }
+    }
+}
+
+fn is_punct(token: &TokenTree, ch: char) -> bool {
+    matches!(token, TokenTree::Punct(punct) if punct.as_char() == ch)
+}
+
+fn joined_punct(tokens: &[TokenTree], index: usize) -> (String, usize) {
+    let mut text = String::new();
+    let mut index = index;
+    while let Some(TokenTree::Punct(punct)) = tokens.get(index) {
+        text.push(punct.as_char());
+        index += 1;
+        if punct.spacing() == Spacing::Alone || punct.as_char() == '\'' {
+            break;
+        }
+    }
+
+    (text, index)
+}
+
+fn continues_after_block(next: Option<&TokenTree>) -> bool {
+    match next {
+        Some(TokenTree::Punct(punct)) => matches!(punct.as_char(), ',' | ';' | '.' | '?'),
+        Some(TokenTree::Ident(ident)) => ident == "else",
+        _ => false,
+    }
+}
+
+fn contains_statement(tokens: &[TokenTree]) -> bool {
+    tokens.iter().any(|token| match token {
+        TokenTree::Group(group) => {
+            let nested: Vec<TokenTree> = group.stream().into_iter().collect();
+            (group.delimiter() == Delimiter::Brace
+                && nested.iter().any(|token| is_punct(token, ';')))
+                || contains_statement(&nested)
+        }
+        _ => false,
+    })
+}
+
+fn split_commas(tokens: &[TokenTree]) -> Vec<&[TokenTree]> {
+    let mut elements = Vec::new();
+    let mut start = 0;
+    let mut angle_depth = 0usize;
+    for (index, token) in tokens.iter().enumerate() {
+        if let TokenTree::Punct(punct) = token {
+            match punct.as_char() {
+                '<' if index > 0 && matches!(tokens[index - 1], TokenTree::Ident(_)) => {
+                    angle_depth += 1
+                }
+                '>' if angle_depth > 0 && !is_punct(&tokens[index - 1], '-') => angle_depth -= 1,
+                ',' if angle_depth == 0 => {
+                    elements.push(&tokens[start..index]);
+                    start = index + 1;
+                }
+                _ => {}
+            }
+        }
+    }
+    if start < tokens.len() {
+        elements.push(&tokens[start..]);
+    }
+
+    elements
+}
@@ -0,0 +1,228 @@
+use proc_macro2::{Ident, Span, TokenStream, TokenTree, Group};
+use quote::ToTokens;
+use std::cell::RefCell;
+use std::collections::HashMap;
+use crate::pointer::{enter_pointer, SharedValueError};
+
+/// Subtrees whose source is shorter than this are cheaper to repeat than to bind.
+const MIN_SHARED_LEN: usize = 16;
+
+thread_local! {
+    static SHARE_STATE: RefCell<Option<ShareState>> = const { RefCell::new(None) };
+}
+
+#[derive(Default)]
+struct ShareState {
+    bindings: Vec<TokenStream>,
+    clones: Vec<Option<TokenStream>>,
+    uses: Vec<usize>,
+    by_text: HashMap<String, usize>,
+    by_pointer: HashMap<usize, usize>,
+    error: Option<SharedValueError>,
+    /// Number of [`unshared`] scopes running, in which nothing is bound or reused.
+    unshared: usize,
+}
+
+impl ShareState {
+    fn ident(index: usize) -> Ident {
+        Ident::new(&format!("__quote_data_shared_{}", index), Span::call_site())
+    }
+
+    fn bind(&mut self, tokens: TokenStream, clone: Option<TokenStream>) -> usize {
+        self.bindings.push(tokens);
+        self.clones.push(clone);
+        self.uses.push(1);
+        self.bindings.len() - 1
+    }
+
+    fn index_of(ident: &Ident) -> Option<usize> {
+        ident
+            .to_string()
+            .strip_prefix("__quote_data_shared_")
+            .and_then(|index| index.parse().ok())
+    }
+
+    fn substitute(&self, tokens: TokenStream, expanded: &[TokenStream]) -> TokenStream {
+        tokens
+            .into_iter()
+            .flat_map(|token| -> Vec<TokenTree> {
+                match token {
+                    TokenTree::Ident(ident) => match Self::index_of(&ident) {
+                        Some(index) if self.uses[index] == 1 => {
+                            expanded[index].clone().into_iter().collect()
+                        }
+                        Some(index) => match &self.clones[index] {
+                            Some(clone) => quote::quote! {#clone(&#ident)},
+                            None => quote::quote! {#ident.clone()},
+                        }
+                        .into_iter()
+                        .collect(),
+                        None => vec![TokenTree::Ident(ident)],
+                    },
+                    TokenTree::Group(group) => {
+                        let mut new_group = Group::new(
+                            group.delimiter(),
+                            self.substitute(group.stream(), expanded),
+                        );
+                        new_group.set_span(group.span());
+                        vec![TokenTree::Group(new_group)]
+                    }
+                    token => vec![token],
+                }
+            })
+            .collect()
+    }
+
+    fn finish(self, root: TokenStream) -> TokenStream {
+        let mut expanded: Vec<TokenStream> = Vec::with_capacity(self.bindings.len());
+        for binding in &self.bindings {
+            let tokens = self.substitute(binding.clone(), &expanded);
+            expanded.push(tokens);
+        }
+
+        let lets: Vec<TokenStream> = expanded
+            .iter()
+            .enumerate()
+            .filter(|(index, _)| self.uses[*index] > 1)
+            .map(|(index, tokens)| {
+                let ident = Self::ident(index);
+                quote::quote! {
+                    let #ident = #tokens;
+                }
+            })
+            .collect();
+        let root = self.substitute(root, &expanded);
+
+        if lets.is_empty() {
+            root
+        } else {
+            quote::quote! {
+                {
+                    #(#lets)*
+                    #root
+                }
+            }
+        }
+    }
+}
+
+/// Quotes `value`, hoisting repeated subtrees and shared `Rc`/`Arc` allocations into `let`
+/// bindings of a block expression.
+///
+/// A shared `Rc` or `Arc` is reused as `Rc::clone(&binding)`/`Arc::clone(&binding)`, so it is
+/// shared again at runtime. Any other repeated subtree is reused as `binding.clone()`, so every
+/// type that can appear more than once in `value`, derived types included, must implement
+/// `Clone`. Items of `#[quote(chunk_ty = ..)]` fields are quoted in full, because the chunk
+/// helper functions can't see the bindings. A reference cycle is reported as an error.
+pub fn quote_shared<T: ToTokens + ?Sized>(value: &T) -> Result<TokenStream, SharedValueError> {
+    let previous = SHARE_STATE.with(|state| state.replace(Some(ShareState::default())));
+    let root = value.to_token_stream();
+    let state = SHARE_STATE
+        .with(|state| state.replace(previous))
+        .unwrap_or_default();
+
+    match state.error.clone() {
+        Some(error) => Err(error),
+        None => Ok(state.finish(root)),
+    }
+}
+
+/// Registers the tokens of a quoted subtree, returning a placeholder for its binding when
+/// called inside `quote_shared`, and `tokens` unchanged otherwise.
+pub fn share_tokens(tokens: TokenStream) -> TokenStream {
+    SHARE_STATE.with(|state| match state.borrow_mut().as_mut() {
+        Some(state) if state.unshared == 0 => {
+            let text = tokens.to_string();
+            if text.len() < MIN_SHARED_LEN {
+                return tokens;
+            }
+
+            let index = match state.by_text.get(&text) {
+                Some(index) => {
+                    state.uses[*index] += 1;
+                    *index
+                }
+                None => {
+                    let index = state.bind(tokens, None);
+                    state.by_text.insert(text, index);
+                    index
+                }
+            };
+
+            ShareState::ident(index).into_token_stream()
+        }
+        _ => tokens,
+    })
+}
+
+/// Runs `f` without binding or reusing subtrees inside `quote_shared`, for tokens the `let`
+/// bindings aren't visible from, like the bodies of chunk helper functions. Cycles are still
+/// reported.
+pub(crate) fn unshared<R>(f: impl FnOnce() -> R) -> R {
+    SHARE_STATE.with(|state| {
+        if let Some(state) = state.borrow_mut().as_mut() {
+            state.unshared += 1;
+        }
+    });
+    let _guard = UnsharedGuard;
+    f()
+}
+
+/// Leaves an [`unshared`] scope when dropped, even if `f` panics.
+struct UnsharedGuard;
+
+impl Drop for UnsharedGuard {
+    fn drop(&mut self) {
+        SHARE_STATE.with(|state| {
+            if let Some(state) = state.borrow_mut().as_mut() {
+                state.unshared -= 1;
+            }
+        });
+    }
+}
+
+/// Quotes the allocation at `address` with `quote`, reusing the binding of an allocation that
+/// was already quoted inside `quote_shared` through `clone`, e.g. `::std::rc::Rc::clone`.
+///
+/// Re-entering an allocation that is still being quoted is a reference cycle: inside
+/// `quote_shared` it is reported as an error, outside of it this panics.
+pub fn share_pointer(
+    address: usize,
+    type_name: &'static str,
+    clone: TokenStream,
+    quote: impl FnOnce() -> TokenStream,
+) -> TokenStream {
+    let shared = SHARE_STATE.with(|state| {
+        state.borrow_mut().as_mut().filter(|state| state.unshared == 0).and_then(|state| {
+            state.by_pointer.get(&address).copied().map(|index| {
+                state.uses[index] += 1;
+                ShareState::ident(index).into_token_stream()
+            })
+        })
+    });
+    if let Some(tokens) = shared {
+        return tokens;
+    }
+
+    let tokens = match enter_pointer(address, type_name) {
+        Ok(_guard) => quote(),
+        Err(error) => {
+            return SHARE_STATE.with(|state| match state.borrow_mut().as_mut() {
+                Some(state) => {
+                    state.error.get_or_insert(error);
+                    quote::quote! {()}
+                }
+                None => panic!("{}", error),
+            });
+        }
+    };
+
+    SHARE_STATE.with(|state| match state.borrow_mut().as_mut() {
+        Some(state) if state.unshared == 0 => {
+            let index = state.bind(tokens, Some(clone));
+            state.by_pointer.insert(address, index);
+            ShareState::ident(index).into_token_stream()
+        }
+        _ => tokens,
+    })
+}
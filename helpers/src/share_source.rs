@@ -0,0 +1,167 @@
+use std::cell::RefCell;
+use std::collections::HashMap;
+use std::fmt::{self, Write as FmtWrite};
+use crate::pointer::{enter_pointer, SharedValueError};
+use crate::source::ToRustSource;
+
+/// Delimits the placeholder of a binding; written sources escape it inside every literal.
+const MARKER: char = '\u{1}';
+
+thread_local! {
+    static SHARE_STATE: RefCell<Option<ShareState>> = const { RefCell::new(None) };
+}
+
+#[derive(Default)]
+struct ShareState {
+    bindings: Vec<String>,
+    clones: Vec<&'static str>,
+    uses: Vec<usize>,
+    by_pointer: HashMap<usize, usize>,
+    error: Option<SharedValueError>,
+    /// Number of [`unshared`] scopes running, in which nothing is bound or reused.
+    unshared: usize,
+}
+
+impl ShareState {
+    fn substitute(&self, source: &str, expanded: &[String], out: &mut String) {
+        let mut parts = source.split(MARKER);
+        out.push_str(parts.next().unwrap_or_default());
+        while let (Some(index), Some(rest)) = (parts.next(), parts.next()) {
+            let index: usize = index.parse().expect("a shared binding placeholder");
+            if self.uses[index] == 1 {
+                out.push_str(&expanded[index]);
+            } else {
+                let _ = write!(out, "{}(&__quote_data_shared_{})", self.clones[index], index);
+            }
+            out.push_str(rest);
+        }
+    }
+
+    fn finish(self, root: &str) -> String {
+        let mut expanded: Vec<String> = Vec::with_capacity(self.bindings.len());
+        for binding in &self.bindings {
+            let mut source = String::new();
+            self.substitute(binding, &expanded, &mut source);
+            expanded.push(source);
+        }
+
+        let mut out = String::new();
+        for (index, source) in expanded.iter().enumerate() {
+            if self.uses[index] > 1 {
+                let _ = write!(out, "let __quote_data_shared_{} = {}; ", index, source);
+            }
+        }
+        if out.is_empty() {
+            self.substitute(root, &expanded, &mut out);
+            return out;
+        }
+
+        let mut block = format!("{{ {}", out);
+        self.substitute(root, &expanded, &mut block);
+        block.push_str(" }");
+        block
+    }
+}
+
+/// Source counterpart of `quote_shared`: writes `value` as a block expression that binds every
+/// `Rc`/`Arc` allocation shared in `value` once with `let` and reuses it through
+/// `Rc::clone`/`Arc::clone`, so the pointers are shared again at runtime.
+///
+/// Unlike `quote_shared`, repeated subtrees that aren't behind a shared pointer are written
+/// again instead of being hoisted, so no type has to implement `Clone`. Like there, items of
+/// `#[quote(chunk_ty = ..)]` fields are written in full.
+/// A reference cycle is reported as an error.
+pub fn shared_rust_source<T: ToRustSource + ?Sized>(
+    value: &T,
+) -> Result<String, SharedValueError> {
+    let previous = SHARE_STATE.with(|state| state.replace(Some(ShareState::default())));
+    let root = value.to_rust_source();
+    let state = SHARE_STATE
+        .with(|state| state.replace(previous))
+        .unwrap_or_default();
+
+    match state.error.clone() {
+        Some(error) => Err(error),
+        None => Ok(state.finish(&root)),
+    }
+}
+
+/// Runs `f` without binding or reusing allocations inside `shared_rust_source`, for source the
+/// `let` bindings aren't visible from, like the bodies of chunk helper functions. Cycles are
+/// still reported.
+pub(crate) fn unshared<R>(f: impl FnOnce() -> R) -> R {
+    SHARE_STATE.with(|state| {
+        if let Some(state) = state.borrow_mut().as_mut() {
+            state.unshared += 1;
+        }
+    });
+    let _guard = UnsharedGuard;
+    f()
+}
+
+/// Leaves an [`unshared`] scope when dropped, even if `f` panics.
+struct UnsharedGuard;
+
+impl Drop for UnsharedGuard {
+    fn drop(&mut self) {
+        SHARE_STATE.with(|state| {
+            if let Some(state) = state.borrow_mut().as_mut() {
+                state.unshared -= 1;
+            }
+        });
+    }
+}
+
+/// Writes the allocation at `address` with `write`, reusing the binding of an allocation that
+/// was already written inside `shared_rust_source` through `clone`, e.g. `::std::rc::Rc::clone`.
+///
+/// Re-entering an allocation that is still being written is a reference cycle: inside
+/// `shared_rust_source` it is reported as an error, outside of it this panics.
+pub(crate) fn write_pointer_source(
+    address: usize,
+    type_name: &'static str,
+    clone: &'static str,
+    out: &mut dyn FmtWrite,
+    write: impl FnOnce(&mut dyn FmtWrite) -> fmt::Result,
+) -> fmt::Result {
+    let shared = SHARE_STATE.with(|state| {
+        state.borrow_mut().as_mut().filter(|state| state.unshared == 0).and_then(|state| {
+            state.by_pointer.get(&address).copied().inspect(|index| {
+                state.uses[*index] += 1;
+            })
+        })
+    });
+    if let Some(index) = shared {
+        return write!(out, "{MARKER}{index}{MARKER}");
+    }
+
+    let _guard = match enter_pointer(address, type_name) {
+        Ok(guard) => guard,
+        Err(error) => {
+            return SHARE_STATE.with(|state| match state.borrow_mut().as_mut() {
+                Some(state) => {
+                    state.error.get_or_insert(error);
+                    out.write_str("()")
+                }
+                None => panic!("{}", error),
+            });
+        }
+    };
+
+    if SHARE_STATE.with(|state| state.borrow().as_ref().is_none_or(|state| state.unshared > 0)) {
+        return write(out);
+    }
+
+    let mut source = String::new();
+    write(&mut source)?;
+    let index = SHARE_STATE.with(|state| {
+        let mut state = state.borrow_mut();
+        let state = state.as_mut().expect("inside `shared_rust_source`");
+        state.bindings.push(source);
+        state.clones.push(clone);
+        state.uses.push(1);
+        state.by_pointer.insert(address, state.bindings.len() - 1);
+        state.bindings.len() - 1
+    });
+    write!(out, "{MARKER}{index}{MARKER}")
+}
@@ -0,0 +1,449 @@
+use std::collections::{HashMap, HashSet};
+use std::fmt::{self, Write as FmtWrite};
+use std::hash::Hash;
+use std::io;
+use std::cell::RefCell;
+use std::marker::PhantomData;
+use std::rc::Rc;
+use std::sync::Arc;
+use crate::context::{non_finite_allowed, non_finite_constant, LiteralPolicy, QuoteContext};
+use crate::emit::{side_file, EmitConfig};
+use crate::share_source::{unshared, write_pointer_source};
+use crate::try_quote::{in_index, record_source, take_source_error, try_write_source, QuoteError};
+#[cfg(feature = "to-token")]
+use crate::TokenizableError;
+
+/// Writes the Rust source of an expression that rebuilds `self`.
+///
+/// This is the `TokenStream`-free counterpart of `quote::ToTokens`: the output is the code the
+/// `to-token` backend produces, apart from whitespace, but it is streamed straight into the
+/// writer, so huge values never have to be held in memory as tokens.
+pub trait ToRustSource {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result;
+
+    /// Writes a `Vec<Self>`; overridden by `u8` to emit a byte string.
+    fn write_vec_source(items: &[Self], out: &mut dyn FmtWrite) -> fmt::Result
+        where
+            Self: Sized,
+    {
+        match EmitConfig::current().chunk_size() {
+            Some(chunk_size) if items.len() > chunk_size => {
+                write_chunked_source(items, chunk_size, None, out)
+            }
+            _ => {
+                out.write_str("vec![")?;
+                write_source_list(items, out)?;
+                out.write_str("]")
+            }
+        }
+    }
+
+    /// Writes a `&[Self]`; overridden by `u8` to emit a byte string.
+    fn write_slice_source(items: &[Self], out: &mut dyn FmtWrite) -> fmt::Result
+        where
+            Self: Sized,
+    {
+        out.write_str("&[")?;
+        write_source_list(items, out)?;
+        out.write_str("]")
+    }
+
+    /// Like `to_rust_source`, returning the failure with the path of the failing field instead
+    /// of panicking, e.g. under `FloatPolicy::Strict`.
+    fn try_to_rust_source(&self) -> Result<String, QuoteError> {
+        let mut source = String::new();
+        try_write_source(self, &mut source)?;
+
+        Ok(source)
+    }
+
+    /// Panics with the failure if the value can't be quoted.
+    fn to_rust_source(&self) -> String {
+        let mut source = String::new();
+        if self.write_source(&mut source).is_err() {
+            match take_source_error() {
+                Some(error) => panic!("{}", error),
+                None => panic!("a formatting trait implementation returned an error"),
+            }
+        }
+
+        source
+    }
+}
+
+/// Streams the source of `value` into an `io::Write`, e.g. a `BufWriter<File>` in a build script.
+pub fn write_rust_source<T, W>(value: &T, writer: &mut W) -> io::Result<()>
+    where
+        T: ToRustSource + ?Sized,
+        W: io::Write,
+{
+    let mut adapter = IoAdapter {
+        inner: writer,
+        error: None,
+    };
+
+    match value.write_source(&mut adapter) {
+        Ok(()) => Ok(()),
+        Err(_) => Err(adapter.error.unwrap_or_else(|| match take_source_error() {
+            Some(error) => io::Error::other(error),
+            None => io::Error::other("formatter error"),
+        })),
+    }
+}
+
+struct IoAdapter<'a, W: io::Write> {
+    inner: &'a mut W,
+    error: Option<io::Error>,
+}
+
+impl<'a, W: io::Write> FmtWrite for IoAdapter<'a, W> {
+    fn write_str(&mut self, s: &str) -> fmt::Result {
+        self.inner.write_all(s.as_bytes()).map_err(|e| {
+            self.error = Some(e);
+            fmt::Error
+        })
+    }
+}
+
+/// Writes `items` separated by `, `.
+pub fn write_source_list<'a, T, I>(items: I, out: &mut dyn FmtWrite) -> fmt::Result
+    where
+        T: ToRustSource + ?Sized + 'a,
+        I: IntoIterator<Item = &'a T>,
+{
+    write_indexed_list(items, 0, out)
+}
+
+/// Writes `items` separated by `, `, the first one being the item at `offset`.
+fn write_indexed_list<'a, T, I>(items: I, offset: usize, out: &mut dyn FmtWrite) -> fmt::Result
+    where
+        T: ToRustSource + ?Sized + 'a,
+        I: IntoIterator<Item = &'a T>,
+{
+    for (index, item) in items.into_iter().enumerate() {
+        if index != 0 {
+            out.write_str(", ")?;
+        }
+        in_index(offset + index, || item.write_source(out))?;
+    }
+
+    Ok(())
+}
+
+/// Source counterpart of `chunked_token_stream`, used for `#[quote(chunk = ...)]` fields.
+pub fn write_chunked_source<T: ToRustSource>(
+    items: &[T],
+    chunk_size: usize,
+    element_type: Option<&str>,
+    out: &mut dyn FmtWrite,
+) -> fmt::Result {
+    if items.len() <= chunk_size {
+        out.write_str("vec![")?;
+        write_source_list(items, out)?;
+        return out.write_str("]");
+    }
+
+    let chunks = items.chunks(chunk_size.max(1));
+    let chunk_count = chunks.len();
+    out.write_str("{ ")?;
+    for (index, chunk) in chunks.enumerate() {
+        match element_type {
+            Some(element_type) => write!(
+                out,
+                "fn __quote_data_chunk_{}() -> ::std::vec::Vec<{}> {{ vec![",
+                index, element_type
+            )?,
+            None if index == 0 => write!(
+                out,
+                "let mut chunked = ::std::vec::Vec::with_capacity({}usize); chunked.extend(vec![",
+                items.len()
+            )?,
+            None => out.write_str("chunked.extend(vec![")?,
+        }
+        let offset = index * chunk_size.max(1);
+        match element_type {
+            // The helper functions can't see the `let` bindings of `shared_rust_source`.
+            Some(_) => unshared(|| write_indexed_list(chunk, offset, out))?,
+            None => write_indexed_list(chunk, offset, out)?,
+        }
+        match element_type {
+            Some(_) => out.write_str("] } ")?,
+            None => out.write_str("]); ")?,
+        }
+    }
+    if element_type.is_some() {
+        write!(
+            out,
+            "let mut chunked = ::std::vec::Vec::with_capacity({}usize); ",
+            items.len()
+        )?;
+        for index in 0..chunk_count {
+            write!(out, "chunked.extend(__quote_data_chunk_{}()); ", index)?;
+        }
+    }
+    out.write_str("chunked }")
+}
+
+/// The type suffix of a number literal, unless the context emits unsuffixed literals.
+fn literal_suffix(ty: &'static str) -> &'static str {
+    match QuoteContext::with(QuoteContext::literal_policy) {
+        LiteralPolicy::Suffixed => ty,
+        LiteralPolicy::Unsuffixed => "",
+    }
+}
+
+macro_rules! suffixed_source {
+    ($($ty:ident),*) => {
+        $(
+            impl ToRustSource for $ty {
+                fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+                    write!(out, "{}{}", self, literal_suffix(stringify!($ty)))
+                }
+            }
+        )*
+    };
+}
+
+suffixed_source!(u16, u32, u64, u128, usize, i8, i16, i32, i64, i128, isize);
+
+impl ToRustSource for u8 {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        write!(out, "{}{}", self, literal_suffix("u8"))
+    }
+
+    fn write_vec_source(items: &[Self], out: &mut dyn FmtWrite) -> fmt::Result {
+        match side_file(items, "bin") {
+            Some(path) => write!(out, "include_bytes!({}).to_vec()", path),
+            None => {
+                write_byte_string(items, out)?;
+                out.write_str(".to_vec()")
+            }
+        }
+    }
+
+    fn write_slice_source(items: &[Self], out: &mut dyn FmtWrite) -> fmt::Result {
+        match side_file(items, "bin") {
+            Some(path) => write!(out, "include_bytes!({})", path),
+            None => write_byte_string(items, out),
+        }
+    }
+}
+
+fn write_byte_string(bytes: &[u8], out: &mut dyn FmtWrite) -> fmt::Result {
+    out.write_str("b\"")?;
+    for (index, byte) in bytes.iter().enumerate() {
+        match byte {
+            b'\0' => match bytes.get(index + 1) {
+                Some(b'0'..=b'7') => out.write_str("\\x00")?,
+                _ => out.write_str("\\0")?,
+            },
+            b'\t' => out.write_str("\\t")?,
+            b'\n' => out.write_str("\\n")?,
+            b'\r' => out.write_str("\\r")?,
+            b'"' => out.write_str("\\\"")?,
+            b'\\' => out.write_str("\\\\")?,
+            b'\x20'..=b'\x7E' => out.write_char(*byte as char)?,
+            _ => write!(out, "\\x{:02X}", byte)?,
+        }
+    }
+    out.write_str("\"")
+}
+
+macro_rules! float_source {
+    ($($ty:ident),*) => {
+        $(
+            impl ToRustSource for $ty {
+                fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+                    match non_finite_constant(*self as f64) {
+                        Some(constant) => {
+                            non_finite_allowed(stringify!($ty), constant).map_err(record_source)?;
+                            write!(out, "::core::{}::{}", stringify!($ty), constant)
+                        }
+                        // Same text as `Literal::f64_suffixed`/`f64_unsuffixed`, e.g. `1f64`
+                        // and `1.0`.
+                        None => match literal_suffix(stringify!($ty)) {
+                            "" if !self.to_string().contains('.') => write!(out, "{}.0", self),
+                            suffix => write!(out, "{}{}", self, suffix),
+                        },
+                    }
+                }
+            }
+        )*
+    };
+}
+
+float_source!(f32, f64);
+
+impl ToRustSource for bool {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        write!(out, "{}", self)
+    }
+}
+
+impl ToRustSource for char {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        write!(out, "{:?}", self)
+    }
+}
+
+impl ToRustSource for str {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        match side_file(self.as_bytes(), "txt") {
+            Some(path) => write!(out, "include_str!({})", path),
+            None => write!(out, "{:?}", self),
+        }
+    }
+}
+
+impl ToRustSource for String {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        self.as_str().write_source(out)?;
+        out.write_str(".to_string()")
+    }
+}
+
+impl<T: ToRustSource + ?Sized> ToRustSource for &T {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        (**self).write_source(out)
+    }
+}
+
+impl<T: ToRustSource> ToRustSource for Vec<T> {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        T::write_vec_source(self, out)
+    }
+}
+
+impl<T: ToRustSource> ToRustSource for [T] {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        T::write_slice_source(self, out)
+    }
+}
+
+impl<T: ToRustSource> ToRustSource for Option<T> {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        match self {
+            Some(value) => {
+                out.write_str("Some(")?;
+                value.write_source(out)?;
+                out.write_str(")")
+            }
+            None => out.write_str("None"),
+        }
+    }
+}
+
+impl<T: ToRustSource, E: ToRustSource> ToRustSource for Result<T, E> {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        match self {
+            Ok(value) => {
+                out.write_str("Ok(")?;
+                value.write_source(out)?;
+            }
+            Err(error) => {
+                out.write_str("Err(")?;
+                error.write_source(out)?;
+            }
+        }
+        out.write_str(")")
+    }
+}
+
+impl<K, V, S> ToRustSource for HashMap<K, V, S>
+    where
+        K: ToRustSource + Eq + Hash,
+        V: ToRustSource,
+{
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        out.write_str("vec![")?;
+        for (index, (key, value)) in self.iter().enumerate() {
+            if index != 0 {
+                out.write_str(", ")?;
+            }
+            out.write_str("(")?;
+            key.write_source(out)?;
+            out.write_str(", ")?;
+            value.write_source(out)?;
+            out.write_str(")")?;
+        }
+        out.write_str("].into_iter().collect()")
+    }
+}
+
+impl<T: ToRustSource + Eq + Hash, S> ToRustSource for HashSet<T, S> {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        out.write_str("vec![")?;
+        write_source_list(self.iter(), out)?;
+        out.write_str("].into_iter().collect()")
+    }
+}
+
+impl<A: ToRustSource, B: ToRustSource> ToRustSource for (A, B) {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        out.write_str("(")?;
+        self.0.write_source(out)?;
+        out.write_str(", ")?;
+        self.1.write_source(out)?;
+        out.write_str(")")
+    }
+}
+
+impl<T: ?Sized> ToRustSource for PhantomData<T> {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        out.write_str("std::marker::PhantomData::default()")
+    }
+}
+
+#[cfg(feature = "to-token")]
+impl ToRustSource for proc_macro2::Span {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        out.write_str("::proc_macro2::Span::call_site()")
+    }
+}
+
+impl<T: ToRustSource + ?Sized> ToRustSource for Box<T> {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        out.write_str("::std::boxed::Box::new(")?;
+        (**self).write_source(out)?;
+        out.write_str(")")
+    }
+}
+
+impl<T: ToRustSource> ToRustSource for Rc<T> {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        let address = Rc::as_ptr(self) as *const () as usize;
+        let type_name = std::any::type_name::<Self>();
+        write_pointer_source(address, type_name, "::std::rc::Rc::clone", out, |out| {
+            out.write_str("::std::rc::Rc::new(")?;
+            (**self).write_source(out)?;
+            out.write_str(")")
+        })
+    }
+}
+
+impl<T: ToRustSource> ToRustSource for Arc<T> {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        let address = Arc::as_ptr(self) as *const () as usize;
+        let type_name = std::any::type_name::<Self>();
+        write_pointer_source(address, type_name, "::std::sync::Arc::clone", out, |out| {
+            out.write_str("::std::sync::Arc::new(")?;
+            (**self).write_source(out)?;
+            out.write_str(")")
+        })
+    }
+}
+
+impl<T: ToRustSource> ToRustSource for RefCell<T> {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        out.write_str("::std::cell::RefCell::new(")?;
+        self.borrow().write_source(out)?;
+        out.write_str(")")
+    }
+}
+
+#[cfg(feature = "to-token")]
+impl ToRustSource for TokenizableError {
+    fn write_source(&self, _out: &mut dyn FmtWrite) -> fmt::Result {
+        unreachable!()
+    }
+}
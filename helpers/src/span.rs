@@ -0,0 +1,64 @@
+use proc_macro2::{Group, Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use std::cell::Cell;
+
+thread_local! {
+    static CURRENT_SPAN: Cell<Option<Span>> = const { Cell::new(None) };
+}
+
+/// Span given to the tokens that are being emitted, `Span::call_site()` by default.
+pub fn current_span() -> Span {
+    CURRENT_SPAN.with(|span| span.get()).unwrap_or_else(Span::call_site)
+}
+
+/// Runs `f` with `span` given to every token the handlers and derived impls emit.
+pub fn with_span<R>(span: Span, f: impl FnOnce() -> R) -> R {
+    let _guard = SpanGuard(CURRENT_SPAN.with(|current| current.replace(Some(span))));
+    f()
+}
+
+/// Restores the previous span when dropped, even if the scope panics.
+struct SpanGuard(Option<Span>);
+
+impl Drop for SpanGuard {
+    fn drop(&mut self) {
+        CURRENT_SPAN.with(|current| current.set(self.0));
+    }
+}
+
+/// Sets the span of every token in `tokens`, including the delimiters of groups.
+pub fn respan(tokens: TokenStream, span: Span) -> TokenStream {
+    tokens
+        .into_iter()
+        .map(|token| match token {
+            TokenTree::Group(group) => {
+                let mut new_group = Group::new(group.delimiter(), respan(group.stream(), span));
+                new_group.set_span(span);
+                TokenTree::Group(new_group)
+            }
+            mut token => {
+                token.set_span(span);
+                token
+            }
+        })
+        .collect()
+}
+
+/// Tokenizes a value with every emitted token pointing at a source span.
+///
+/// Downstream diagnostics about the generated code then point at the attribute or literal the
+/// value came from instead of the macro invocation.
+pub trait ToTokensSpanned: ToTokens {
+    fn to_tokens_spanned(&self, span: Span, tokens: &mut TokenStream) {
+        with_span(span, || self.to_tokens(tokens))
+    }
+
+    fn to_token_stream_spanned(&self, span: Span) -> TokenStream {
+        let mut tokens = TokenStream::new();
+        self.to_tokens_spanned(span, &mut tokens);
+
+        tokens
+    }
+}
+
+impl<T: ToTokens + ?Sized> ToTokensSpanned for T {}
@@ -0,0 +1,130 @@
+use proc_macro2::{Group, Ident, Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use std::fmt::{self, Write as FmtWrite};
+use crate::source::ToRustSource;
+
+/// Code pasted into the output as it is instead of being quoted as data.
+///
+/// `Tokens` is emitted unchanged, e.g. a reference to a runtime variable or `env!("X")`.
+/// `Hole` is emitted as `#name`, a slot that is filled later with [`fill_holes`] or by passing
+/// the output through `quote::quote!` with a variable called `name` in scope.
+#[derive(Clone, Debug)]
+pub enum Splice {
+    Tokens(TokenStream),
+    Hole(Ident),
+}
+
+impl Splice {
+    pub fn new(tokens: impl ToTokens) -> Self {
+        Splice::Tokens(tokens.into_token_stream())
+    }
+
+    pub fn hole(name: &str) -> Self {
+        Splice::Hole(Ident::new(name, Span::call_site()))
+    }
+}
+
+impl From<TokenStream> for Splice {
+    fn from(tokens: TokenStream) -> Self {
+        Splice::Tokens(tokens)
+    }
+}
+
+impl ToTokens for Splice {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        match self {
+            Splice::Tokens(value) => value.to_tokens(tokens),
+            Splice::Hole(name) => (quote::quote! {
+                ##name
+            }).to_tokens(tokens),
+        }
+    }
+}
+
+impl ToRustSource for Splice {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        match self {
+            Splice::Tokens(value) => write!(out, "{}", value),
+            Splice::Hole(name) => write!(out, "#{}", name),
+        }
+    }
+}
+
+/// Replaces every `#name` hole in `tokens` for which `fill` returns tokens.
+///
+/// Holes that `fill` returns `None` for are kept, so templates can be filled in several steps.
+pub fn fill_holes(
+    tokens: TokenStream,
+    fill: &mut impl FnMut(&Ident) -> Option<TokenStream>,
+) -> TokenStream {
+    let tokens: Vec<TokenTree> = tokens.into_iter().collect();
+    let mut filled = TokenStream::new();
+    let mut index = 0;
+
+    while index < tokens.len() {
+        match (&tokens[index], tokens.get(index + 1)) {
+            (TokenTree::Punct(punct), Some(TokenTree::Ident(name))) if punct.as_char() == '#' => {
+                match fill(name) {
+                    Some(value) => filled.extend(value),
+                    None => filled.extend(tokens[index..index + 2].iter().cloned()),
+                }
+                index += 2;
+            }
+            (TokenTree::Group(group), _) => {
+                let mut new_group = Group::new(group.delimiter(), fill_holes(group.stream(), fill));
+                new_group.set_span(group.span());
+                filled.extend([TokenTree::Group(new_group)]);
+                index += 1;
+            }
+            (token, _) => {
+                filled.extend([token.clone()]);
+                index += 1;
+            }
+        }
+    }
+
+    filled
+}
+
+/// Tokenizes the value behind a `#[quote(raw)]` field.
+pub struct TokenizableRaw<'a, T: ?Sized>(pub &'a T);
+
+impl<'a, T: ToTokens + ?Sized> TokenizableRaw<'a, T> {
+    pub fn new(value: &'a T) -> Self {
+        TokenizableRaw(value)
+    }
+}
+
+impl<'a, T: ToTokens + ?Sized> ToTokens for TokenizableRaw<'a, T> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        self.0.to_tokens(tokens)
+    }
+}
+
+impl<'a, T: ToTokens + ?Sized> ToRustSource for TokenizableRaw<'a, T> {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        write!(out, "{}", self.0.to_token_stream())
+    }
+}
+
+/// Tokenizes the value behind a `#[quote(with = "...")]` field with the given function, e.g.
+/// the `quote_remote` of a remote definition.
+pub struct TokenizableWith<'a, T: ?Sized>(pub &'a T, pub fn(&T) -> TokenStream);
+
+impl<'a, T: ?Sized> TokenizableWith<'a, T> {
+    pub fn new(value: &'a T, quote: fn(&T) -> TokenStream) -> Self {
+        TokenizableWith(value, quote)
+    }
+}
+
+impl<'a, T: ?Sized> ToTokens for TokenizableWith<'a, T> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        (self.1)(self.0).to_tokens(tokens)
+    }
+}
+
+impl<'a, T: ?Sized> ToRustSource for TokenizableWith<'a, T> {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        write!(out, "{}", (self.1)(self.0))
+    }
+}
@@ -0,0 +1,75 @@
+use std::fmt::{self, Write as FmtWrite};
+use crate::source::ToRustSource;
+use crate::try_quote::in_index;
+
+/// Source counterpart of `TokenizableSlice`, writing a `Vec` as a `&[..]` slice of converted
+/// items for `target_ty` fields.
+pub struct TargetSlice<'a, T, W>(pub &'a [T], pub fn(&'a T) -> W);
+
+impl<'a, T, W: ToRustSource> TargetSlice<'a, T, W> {
+    pub fn new(value: &'a [T], write: fn(&'a T) -> W) -> Self {
+        TargetSlice(value, write)
+    }
+}
+
+impl<'a, T, W: ToRustSource> ToRustSource for TargetSlice<'a, T, W> {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        out.write_str("&[")?;
+        for (index, item) in self.0.iter().enumerate() {
+            if index != 0 {
+                out.write_str(", ")?;
+            }
+            in_index(index, || (self.1)(item).write_source(out))?;
+        }
+        out.write_str("]")
+    }
+}
+
+/// Source counterpart of `TokenizableVec` for `target_ty` fields, converting every item.
+pub struct TargetVec<'a, T, W>(pub &'a [T], pub fn(&'a T) -> W);
+
+impl<'a, T, W: ToRustSource> TargetVec<'a, T, W> {
+    pub fn new(value: &'a [T], write: fn(&'a T) -> W) -> Self {
+        TargetVec(value, write)
+    }
+}
+
+impl<'a, T, W: ToRustSource> ToRustSource for TargetVec<'a, T, W> {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        let items: Vec<W> = self.0.iter().map(self.1).collect();
+        W::write_vec_source(&items, out)
+    }
+}
+
+/// Source counterpart of `TokenizableOption` for `target_ty` fields, converting the content.
+pub struct TargetOption<'a, T, W>(pub Option<&'a T>, pub fn(&'a T) -> W);
+
+impl<'a, T, W: ToRustSource> TargetOption<'a, T, W> {
+    pub fn new(value: Option<&'a T>, write: fn(&'a T) -> W) -> Self {
+        TargetOption(value, write)
+    }
+}
+
+impl<'a, T, W: ToRustSource> ToRustSource for TargetOption<'a, T, W> {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        self.0.map(self.1).write_source(out)
+    }
+}
+
+/// Source counterpart of `TokenizableBox` for `target_ty` fields, converting the content.
+pub struct TargetBox<'a, T: ?Sized, W>(pub &'a T, pub fn(&'a T) -> W);
+
+impl<'a, T: ?Sized, W: ToRustSource> TargetBox<'a, T, W> {
+    /// `value` is the content of the box.
+    pub fn new(value: &'a T, write: fn(&'a T) -> W) -> Self {
+        TargetBox(value, write)
+    }
+}
+
+impl<'a, T: ?Sized, W: ToRustSource> ToRustSource for TargetBox<'a, T, W> {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        out.write_str("::std::boxed::Box::new(")?;
+        (self.1)(self.0).write_source(out)?;
+        out.write_str(")")
+    }
+}
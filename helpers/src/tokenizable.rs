@@ -1,122 +1,461 @@
-use quote::ToTokens;
-use syn::{Type, Error};
-use proc_macro2::TokenStream;
-use crate::helper::{assert_angle_args, get_nested_types};
+use quote::{format_ident, ToTokens};
+use syn::{Type, Error, Ident};
+use proc_macro2::{Literal, TokenStream};
+use crate::emit::{side_file, EmitConfig};
+use crate::helper::{assert_angle_args, get_nested_types, is_slice_of, is_type};
 use crate::error::QuoteItError;
-use std::hash::Hash;
+use crate::share::{share_pointer, share_tokens, unshared};
+use crate::span::{current_span, respan};
+use crate::context::{non_finite_allowed, non_finite_constant, LiteralPolicy, QuoteContext};
+use crate::try_quote::{in_index, report};
+use std::cell::RefCell;
 use std::collections::{HashMap, HashSet};
-use crate::TokenizableError;
-
-pub fn get_value_wrapper(ty: &Type, value_path: TokenStream, as_ref: bool, clone: bool) -> Result<TokenStream, Error> {
-    let ref_token = if as_ref {
-        quote::quote! {&}
-    } else {
-        TokenStream::new()
-    };
-
-    let clone_token = if clone {
-        quote::quote! {.clone()}
+use std::rc::Rc;
+use std::sync::Arc;
+
+/// Builds the expression of a tokenizable value for a value of type `ty`.
+///
+/// `value_path` is a place of type `ty` when `as_ref` is set, and a reference to one otherwise.
+/// The wrappers only borrow the value, so nothing is cloned before tokenizing.
+pub fn get_value_wrapper(ty: &Type, value_path: TokenStream, as_ref: bool) -> Result<TokenStream, Error> {
+    let value_ref = if as_ref {
+        quote::quote! {&#value_path}
     } else {
-        TokenStream::new()
+        value_path
     };
 
     let handlers = [
-        TokenizableVec::<String>::convert_token_stream,
+        TokenizableBytes::convert_token_stream,
+        TokenizableByteSlice::convert_token_stream,
+        TokenizableStr::convert_token_stream,
+        TokenizableVec::<String, TokenStream>::convert_token_stream,
         TokenizableString::convert_token_stream,
-        TokenizableOption::<String>::convert_token_stream,
-        TokenizableResult::<String, TokenizableError>::convert_token_stream,
-        TokenizableHashMap::<String, String>::convert_token_stream,
-        TokenizableHashSet::<String>::convert_token_stream,
-        TokenizablePair::<String, String>::convert_token_stream,
-        TokenizablePhantomData::convert_token_stream
+        TokenizableOption::<String, TokenStream>::convert_token_stream,
+        TokenizableResult::<String, String, TokenStream, TokenStream>::convert_token_stream,
+        TokenizableHashMap::<String, String, TokenStream, TokenStream>::convert_token_stream,
+        TokenizableHashSet::<String, TokenStream>::convert_token_stream,
+        TokenizablePair::<TokenStream, TokenStream>::convert_token_stream,
+        TokenizablePhantomData::convert_token_stream,
+        TokenizablePrimitive::<bool>::convert_token_stream,
+        TokenizableSpan::convert_token_stream,
+        TokenizableRc::<String, TokenStream>::convert_token_stream,
+        TokenizableArc::<String, TokenStream>::convert_token_stream,
+        TokenizableRefCell::<String>::convert_token_stream,
+        TokenizableBox::<String, TokenStream>::convert_token_stream,
     ];
 
     let result = handlers.iter().try_fold(
         None,
         |prev, handler| {
             if prev.is_none() {
-                handler(ty, &value_path)
+                handler(ty, &value_ref)
             } else {
                 Ok(prev)
             }
         }
     )?;
 
-    Ok(result.unwrap_or_else(|| quote::quote! {
-        #ref_token#value_path#clone_token
-    }))
+    Ok(result.unwrap_or(value_ref))
 }
 
-pub trait Tokenizable: ToTokens + Clone + Sized {
-    type ValueType;
-
+pub trait Tokenizable: ToTokens {
     fn value_token_stream(&self) -> TokenStream;
 
-    fn from_value(value: Self::ValueType) -> Self;
-
+    /// Returns the wrapper expression for `ty`, or `None` if this wrapper doesn't handle it.
+    ///
+    /// `value_path` always evaluates to a reference to the value.
     fn convert_token_stream(
         ty: &Type,
         value_path: &TokenStream,
     ) -> Result<Option<TokenStream>, Error>;
 }
 
+/// Returns the single type parameter of `ty` if its last segment is `name`.
+fn nested_type<'a>(ty: &'a Type, name: &'static str) -> Result<Option<&'a Type>, Error> {
+    if let Type::Path(type_path) = ty {
+        let last_segment = type_path.path.segments.last().unwrap();
 
-#[derive(Clone)]
-pub struct TokenizableVec<T: ToTokens + Clone>(pub Vec<T>);
+        if last_segment.ident != name {
+            return Ok(None)
+        }
 
-impl<T: ToTokens + Clone> Tokenizable for TokenizableVec<T> {
-    type ValueType = Vec<T>;
+        let arguments = assert_angle_args(&last_segment.arguments)?;
+        match get_nested_types(arguments).first() {
+            Some(nested_type) => Ok(Some(*nested_type)),
+            None => Err(QuoteItError::TypeParamCountError(name, 1, 0).into_syn_error(ty))
+        }
+    } else {
+        Ok(None)
+    }
+}
 
-    fn value_token_stream(&self) -> TokenStream {
-        let value = &self.0;
-        quote::quote! {
-            vec![#(#value),*]
+/// Returns the two type parameters of `ty` if its last segment is `name`.
+fn nested_type_pair<'a>(ty: &'a Type, name: &'static str) -> Result<Option<(&'a Type, &'a Type)>, Error> {
+    if let Type::Path(type_path) = ty {
+        let last_segment = type_path.path.segments.last().unwrap();
+
+        if last_segment.ident != name {
+            return Ok(None)
+        }
+
+        let arguments = assert_angle_args(&last_segment.arguments)?;
+        let nested_types = get_nested_types(arguments);
+
+        let first_param = nested_types.first().ok_or_else(|| {
+            QuoteItError::TypeParamCountError(name, 2, 0).into_syn_error(ty)
+        })?;
+
+        let second_param = nested_types.get(1).ok_or_else(|| {
+            QuoteItError::TypeParamCountError(name, 2, 1).into_syn_error(ty)
+        })?;
+
+        Ok(Some((*first_param, *second_param)))
+    } else {
+        Ok(None)
+    }
+}
+
+/// Closure turning a reference named `binding` into the wrapper of `ty`.
+fn wrapper_closure(ty: &Type, binding: &str) -> Result<TokenStream, Error> {
+    let binding = format_ident!("{}", binding);
+    let wrapped_value = get_value_wrapper(ty, binding.to_token_stream(), false)?;
+
+    Ok(quote::quote! {
+        |#binding| #wrapped_value
+    })
+}
+
+/// Builds the expression of a tokenizable value emitting a value of type `ty` as one of type
+/// `target`, which the derive checks the handlers can produce.
+///
+/// Strings become `&str`s, `Vec`s slices, and `Vec`, `Option` and `Box` convert their items.
+/// Any other type must be the same in `target`.
+pub fn get_target_wrapper(
+    ty: &Type,
+    target: &Type,
+    value_path: TokenStream,
+    as_ref: bool,
+) -> Result<TokenStream, Error> {
+    let value_ref = if as_ref {
+        quote::quote! {&#value_path}
+    } else {
+        value_path
+    };
+
+    target_wrapper(ty, target, value_ref, false)
+}
+
+/// Like [`get_target_wrapper`], building a `ToRustSource` value that writes the same source.
+pub fn get_target_source(
+    ty: &Type,
+    target: &Type,
+    value_path: TokenStream,
+    as_ref: bool,
+) -> Result<TokenStream, Error> {
+    let value_ref = if as_ref {
+        quote::quote! {&#value_path}
+    } else {
+        value_path
+    };
+
+    target_wrapper(ty, target, value_ref, true)
+}
+
+/// Builds the tokenizable value, or with `source` the `ToRustSource` value, of the reference
+/// `value_ref` emitted as a `target`.
+fn target_wrapper(ty: &Type, target: &Type, value_ref: TokenStream, source: bool) -> Result<TokenStream, Error> {
+    if type_source(ty) == type_source(target) {
+        return match source {
+            true => Ok(value_ref),
+            false => get_value_wrapper(ty, value_ref, false),
+        };
+    }
+
+    if let Type::Reference(reference) = target {
+        let is_str = match ty {
+            Type::Reference(source) => is_type(&source.elem, "str"),
+            _ => is_type(ty, "String"),
+        };
+        if is_type(&reference.elem, "str") && is_str {
+            let value = quote::quote! {::core::convert::AsRef::<str>::as_ref(#value_ref)};
+            return Ok(match source {
+                true => value,
+                false => quote::quote! {quote_data::TokenizableStr::new(#value)},
+            });
         }
+        if let (Type::Slice(slice), Some(item)) = (&*reference.elem, nested_type(ty, "Vec")?) {
+            if is_type(&slice.elem, "u8") && is_type(item, "u8") {
+                let value = quote::quote! {&(#value_ref)[..]};
+                return Ok(match source {
+                    true => value,
+                    false => quote::quote! {quote_data::TokenizableByteSlice::new(#value)},
+                });
+            }
+            let quote = target_closure(item, &slice.elem, "item", source)?;
+            let wrapper = target_wrapper_ident("Slice", source);
+            return Ok(quote::quote! {
+                quote_data::#wrapper::new(&(#value_ref)[..], #quote)
+            });
+        }
+    }
+
+    if let (Some(item), Some(target_item)) = (nested_type(ty, "Vec")?, nested_type(target, "Vec")?) {
+        let quote = target_closure(item, target_item, "item", source)?;
+        let wrapper = target_wrapper_ident("Vec", source);
+        return Ok(quote::quote! {
+            quote_data::#wrapper::new(&(#value_ref)[..], #quote)
+        });
+    }
+    if let (Some(item), Some(target_item)) = (nested_type(ty, "Option")?, nested_type(target, "Option")?) {
+        let quote = target_closure(item, target_item, "option_value", source)?;
+        let wrapper = target_wrapper_ident("Option", source);
+        return Ok(quote::quote! {
+            quote_data::#wrapper::new((#value_ref).as_ref(), #quote)
+        });
+    }
+    if let (Some(item), Some(target_item)) = (nested_type(ty, "Box")?, nested_type(target, "Box")?) {
+        let quote = target_closure(item, target_item, "boxed", source)?;
+        let wrapper = target_wrapper_ident("Box", source);
+        return Ok(quote::quote! {
+            quote_data::#wrapper::new(&**(#value_ref), #quote)
+        });
+    }
+
+    Err(QuoteItError::TargetTypeError(type_source(ty), type_source(target)).into_syn_error(target))
+}
+
+/// `Tokenizable{kind}`, or `Target{kind}` for the source backend.
+fn target_wrapper_ident(kind: &str, source: bool) -> Ident {
+    match source {
+        true => format_ident!("Target{}", kind),
+        false => format_ident!("Tokenizable{}", kind),
     }
+}
+
+fn target_closure(ty: &Type, target: &Type, binding: &str, source: bool) -> Result<TokenStream, Error> {
+    let binding = format_ident!("{}", binding);
+    let wrapped_value = target_wrapper(ty, target, binding.to_token_stream(), source)?;
+
+    Ok(quote::quote! {
+        |#binding| #wrapped_value
+    })
+}
+
+fn type_source(ty: &Type) -> String {
+    ty.to_token_stream()
+        .to_string()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect()
+}
 
-    fn from_value(value: Self::ValueType) -> Self {
-        TokenizableVec(value)
+pub struct TokenizableVec<'a, T, W>(pub &'a [T], pub fn(&'a T) -> W);
+
+impl<'a, T, W: ToTokens> TokenizableVec<'a, T, W> {
+    pub fn new(value: &'a [T], quote: fn(&'a T) -> W) -> Self {
+        TokenizableVec(value, quote)
+    }
+}
+
+impl<'a, T, W: ToTokens> Tokenizable for TokenizableVec<'a, T, W> {
+    fn value_token_stream(&self) -> TokenStream {
+        let span = current_span();
+        match EmitConfig::current().chunk_size() {
+            Some(chunk_size) if self.0.len() > chunk_size => {
+                chunked_token_stream(self.0, self.1, chunk_size, None)
+            }
+            _ => {
+                let value = self
+                    .0
+                    .iter()
+                    .enumerate()
+                    .map(|(index, item)| in_index(index, || (self.1)(item).into_token_stream()));
+                quote::quote_spanned! {span=>
+                    vec![#(#value),*]
+                }
+            }
+        }
     }
 
     fn convert_token_stream(
         ty: &Type,
         value_path: &TokenStream,
     ) -> Result<Option<TokenStream>, Error> {
-        if let Type::Path(type_path) = ty {
-            let last_segment = type_path.path.segments.last().unwrap();
+        let nested_type = match nested_type(ty, "Vec")? {
+            Some(nested_type) => nested_type,
+            None => return Ok(None)
+        };
+        let quote = wrapper_closure(nested_type, "item")?;
+
+        Ok(Some(quote::quote! {
+            quote_data::TokenizableVec::new(&(#value_path)[..], #quote)
+        }))
+    }
+}
 
-            if last_segment.ident != "Vec" {
-                return Ok(None)
+impl<'a, T, W: ToTokens> ToTokens for TokenizableVec<'a, T, W> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let value = self.value_token_stream();
+        (quote::quote! {
+                #value
+        }).to_tokens(tokens)
+    }
+}
+
+/// A `Vec` emitted as a `&[..]` slice, for `target_ty = "&'static [T]"` fields.
+pub struct TokenizableSlice<'a, T, W>(pub &'a [T], pub fn(&'a T) -> W);
+
+impl<'a, T, W: ToTokens> TokenizableSlice<'a, T, W> {
+    pub fn new(value: &'a [T], quote: fn(&'a T) -> W) -> Self {
+        TokenizableSlice(value, quote)
+    }
+}
+
+impl<'a, T, W: ToTokens> ToTokens for TokenizableSlice<'a, T, W> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let span = current_span();
+        let value = self
+            .0
+            .iter()
+            .enumerate()
+            .map(|(index, item)| in_index(index, || (self.1)(item).into_token_stream()));
+        (quote::quote_spanned! {span=>
+            &[#(#value),*]
+        }).to_tokens(tokens)
+    }
+}
+
+/// Emits `items` as a block that builds the `Vec` chunk by chunk.
+///
+/// With an `element_type` every chunk becomes its own helper function, so rustc type-checks
+/// and codegens each chunk separately; otherwise the chunks are `extend`ed inline.
+pub fn chunked_token_stream<'a, T, W: ToTokens>(
+    items: &'a [T],
+    quote: fn(&'a T) -> W,
+    chunk_size: usize,
+    element_type: Option<&TokenStream>,
+) -> TokenStream {
+    let len = items.len();
+    let span = current_span();
+    let quote_chunks = || -> Vec<TokenStream> {
+        items
+            .chunks(chunk_size.max(1))
+            .enumerate()
+            .map(|(chunk_index, chunk)| {
+                let offset = chunk_index * chunk_size.max(1);
+                let chunk = chunk.iter().enumerate().map(|(index, item)| {
+                    in_index(offset + index, || quote(item).into_token_stream())
+                });
+                quote::quote_spanned! {span=>
+                    vec![#(#chunk),*]
+                }
+            })
+            .collect()
+    };
+    // The helper functions can't see the `let` bindings hoisted by `quote_shared`.
+    let chunks = match element_type {
+        Some(_) => unshared(quote_chunks),
+        None => quote_chunks(),
+    };
+
+    match element_type {
+        Some(element_type) => {
+            let helpers: Vec<Ident> = (0..chunks.len())
+                .map(|index| format_ident!("__quote_data_chunk_{}", index))
+                .collect();
+
+            quote::quote_spanned! {span=>
+                {
+                    #(
+                        fn #helpers() -> ::std::vec::Vec<#element_type> {
+                            #chunks
+                        }
+                    )*
+                    let mut chunked = ::std::vec::Vec::with_capacity(#len);
+                    #(chunked.extend(#helpers());)*
+                    chunked
+                }
+            }
+        }
+        None => quote::quote_spanned! {span=>
+            {
+                let mut chunked = ::std::vec::Vec::with_capacity(#len);
+                #(chunked.extend(#chunks);)*
+                chunked
             }
+        }
+    }
+}
 
-            let arguments = assert_angle_args(&last_segment.arguments)?;
-            let nested_types = get_nested_types(arguments);
-            let nested_type = match nested_types.first() {
-                Some(r) => r,
-                None => return Err(QuoteItError::TypeParamCountError("Vec", 1, 0).into_syn_error(ty))
-            };
+pub struct TokenizableChunkedVec<'a, T, W> {
+    pub value: &'a [T],
+    pub quote: fn(&'a T) -> W,
+    pub chunk_size: usize,
+    pub element_type: Option<TokenStream>,
+}
 
-            let wrapped_value = get_value_wrapper(
-                nested_type,
-                quote::quote! {
-                item
-            },
-                false,
-                true,
-            )?;
+impl<'a, T, W: ToTokens> TokenizableChunkedVec<'a, T, W> {
+    pub fn new(
+        value: &'a [T],
+        quote: fn(&'a T) -> W,
+        chunk_size: usize,
+        element_type: Option<TokenStream>,
+    ) -> Self {
+        TokenizableChunkedVec {
+            value,
+            quote,
+            chunk_size,
+            element_type,
+        }
+    }
 
-            Ok(Some(quote::quote! {
-                quote_data::TokenizableVec::from_value(#value_path.iter().map(
-                    |item| #wrapped_value
-                ).collect())
-            }))
+    pub fn value_token_stream(&self) -> TokenStream {
+        let span = current_span();
+        if self.value.len() > self.chunk_size {
+            chunked_token_stream(self.value, self.quote, self.chunk_size, self.element_type.as_ref())
         } else {
-            Ok(None)
+            let value = self
+                .value
+                .iter()
+                .enumerate()
+                .map(|(index, item)| in_index(index, || (self.quote)(item).into_token_stream()));
+            quote::quote_spanned! {span=>
+                vec![#(#value),*]
+            }
         }
     }
+
+    /// Handler used for fields with `#[quote(chunk = ...)]`.
+    pub fn convert_token_stream(
+        ty: &Type,
+        value_path: &TokenStream,
+        chunk_size: usize,
+        element_type: Option<&Type>,
+    ) -> Result<TokenStream, Error> {
+        let nested_type = match nested_type(ty, "Vec")? {
+            Some(nested_type) => nested_type,
+            None => return Err(QuoteItError::ChunkNotVec.into_syn_error(ty))
+        };
+        let quote = wrapper_closure(nested_type, "item")?;
+        let element_type = match element_type {
+            Some(element_type) => quote::quote! {
+                Some(quote::quote! {#element_type})
+            },
+            None => quote::quote! {None}
+        };
+
+        Ok(quote::quote! {
+            quote_data::TokenizableChunkedVec::new(
+                &(#value_path)[..],
+                #quote,
+                #chunk_size,
+                #element_type
+            )
+        })
+    }
 }
 
-impl<T: ToTokens + Clone> ToTokens for TokenizableVec<T> {
+impl<'a, T, W: ToTokens> ToTokens for TokenizableChunkedVec<'a, T, W> {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let value = self.value_token_stream();
         (quote::quote! {
@@ -125,23 +464,27 @@ impl<T: ToTokens + Clone> ToTokens for TokenizableVec<T> {
     }
 }
 
-#[derive(Clone, Eq, PartialEq, Hash)]
-pub struct TokenizableString(pub String);
+pub struct TokenizableString<'a>(pub &'a str);
 
-impl Tokenizable for TokenizableString {
-    type ValueType = String;
+impl<'a> TokenizableString<'a> {
+    pub fn new(value: &'a str) -> Self {
+        TokenizableString(value)
+    }
+}
 
+impl<'a> Tokenizable for TokenizableString<'a> {
     fn value_token_stream(&self) -> TokenStream {
-        let value = &self.0;
-        quote::quote! {
-            #value.to_string()
+        let value = self.0;
+        match side_file(value.as_bytes(), "txt") {
+            Some(path) => respan(quote::quote! {
+                include_str!(#path).to_string()
+            }, current_span()),
+            None => share_tokens(respan(quote::quote! {
+                #value.to_string()
+            }, current_span()))
         }
     }
 
-    fn from_value(value: Self::ValueType) -> Self {
-        TokenizableString(value)
-    }
-
     fn convert_token_stream(
         ty: &Type,
         value_path: &TokenStream,
@@ -154,7 +497,7 @@ impl Tokenizable for TokenizableString {
             }
 
             Ok(Some(quote::quote! {
-                quote_data::TokenizableString::from_value(#value_path.clone())
+                quote_data::TokenizableString::new((#value_path).as_str())
             }))
         } else {
             Ok(None)
@@ -162,7 +505,7 @@ impl Tokenizable for TokenizableString {
     }
 }
 
-impl ToTokens for TokenizableString {
+impl<'a> ToTokens for TokenizableString<'a> {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let value = self.value_token_stream();
         (quote::quote! {
@@ -171,64 +514,136 @@ impl ToTokens for TokenizableString {
     }
 }
 
-#[derive(Clone, Eq, PartialEq, Hash)]
-pub struct TokenizableOption<T: ToTokens + Clone>(pub Option<T>);
+pub struct TokenizableStr<'a>(pub &'a str);
 
-impl<T: ToTokens + Clone> Tokenizable for TokenizableOption<T> {
-    type ValueType = Option<T>;
+impl<'a> TokenizableStr<'a> {
+    pub fn new(value: &'a str) -> Self {
+        TokenizableStr(value)
+    }
+}
 
+impl<'a> Tokenizable for TokenizableStr<'a> {
     fn value_token_stream(&self) -> TokenStream {
-        match &self.0 {
-            Some(nested) => quote::quote! {
-                Some(#nested)
+        let value = self.0;
+        let tokens = match side_file(value.as_bytes(), "txt") {
+            Some(path) => quote::quote! {
+                include_str!(#path)
             },
             None => quote::quote! {
-                None
-            },
+                #value
+            }
+        };
+
+        respan(tokens, current_span())
+    }
+
+    fn convert_token_stream(
+        ty: &Type,
+        value_path: &TokenStream,
+    ) -> Result<Option<TokenStream>, Error> {
+        match ty {
+            Type::Reference(reference) if is_type(&reference.elem, "str") => {
+                Ok(Some(quote::quote! {
+                    quote_data::TokenizableStr::new(*#value_path)
+                }))
+            }
+            _ => Ok(None)
         }
     }
+}
+
+impl<'a> ToTokens for TokenizableStr<'a> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let value = self.value_token_stream();
+        (quote::quote! {
+                #value
+        }).to_tokens(tokens)
+    }
+}
+
+pub struct TokenizableBytes<'a>(pub &'a [u8]);
+
+impl<'a> TokenizableBytes<'a> {
+    pub fn new(value: &'a [u8]) -> Self {
+        TokenizableBytes(value)
+    }
+}
+
+impl<'a> Tokenizable for TokenizableBytes<'a> {
+    fn value_token_stream(&self) -> TokenStream {
+        let tokens = match side_file(self.0, "bin") {
+            Some(path) => quote::quote! {
+                include_bytes!(#path).to_vec()
+            },
+            None => {
+                let value = Literal::byte_string(self.0);
+                quote::quote! {
+                    #value.to_vec()
+                }
+            }
+        };
 
-    fn from_value(value: Self::ValueType) -> Self {
-        TokenizableOption(value)
+        respan(tokens, current_span())
     }
 
     fn convert_token_stream(
         ty: &Type,
         value_path: &TokenStream,
     ) -> Result<Option<TokenStream>, Error> {
-        if let Type::Path(type_path) = ty {
-            let last_segment = type_path.path.segments.last().unwrap();
+        match nested_type(ty, "Vec")? {
+            Some(nested_type) if is_type(nested_type, "u8") => Ok(Some(quote::quote! {
+                quote_data::TokenizableBytes::new(&(#value_path)[..])
+            })),
+            _ => Ok(None)
+        }
+    }
+}
 
-            if last_segment.ident != "Option" {
-                return Ok(None)
-            }
+impl<'a> ToTokens for TokenizableBytes<'a> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let value = self.value_token_stream();
+        (quote::quote! {
+                #value
+        }).to_tokens(tokens)
+    }
+}
 
-            let arguments = assert_angle_args(&last_segment.arguments)?;
-            let nested_types = get_nested_types(arguments);
-            let nested_type = match nested_types.first() {
-                Some(r) => r,
-                None => return Err(QuoteItError::TypeParamCountError("Option", 1, 0).into_syn_error(ty))
-            };
+pub struct TokenizableByteSlice<'a>(pub &'a [u8]);
 
-            let wrapped_value = get_value_wrapper(
-                nested_type,
-                quote::quote! {
-                option_value
+impl<'a> TokenizableByteSlice<'a> {
+    pub fn new(value: &'a [u8]) -> Self {
+        TokenizableByteSlice(value)
+    }
+}
+
+impl<'a> Tokenizable for TokenizableByteSlice<'a> {
+    fn value_token_stream(&self) -> TokenStream {
+        let tokens = match side_file(self.0, "bin") {
+            Some(path) => quote::quote! {
+                include_bytes!(#path)
             },
-                false,
-                true,
-            )?;
+            None => Literal::byte_string(self.0).into_token_stream()
+        };
 
-            Ok(Some(quote::quote! {
-                quote_data::TokenizableOption::from_value(#value_path.as_ref().map(|option_value| #wrapped_value))
-            }))
-        } else {
-            Ok(None)
+        respan(tokens, current_span())
+    }
+
+    fn convert_token_stream(
+        ty: &Type,
+        value_path: &TokenStream,
+    ) -> Result<Option<TokenStream>, Error> {
+        match ty {
+            Type::Reference(reference) if is_slice_of(&reference.elem, "u8") => {
+                Ok(Some(quote::quote! {
+                    quote_data::TokenizableByteSlice::new(*#value_path)
+                }))
+            }
+            _ => Ok(None)
         }
     }
 }
 
-impl<T: ToTokens + Clone> ToTokens for TokenizableOption<T> {
+impl<'a> ToTokens for TokenizableByteSlice<'a> {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let value = self.value_token_stream();
         (quote::quote! {
@@ -237,90 +652,113 @@ impl<T: ToTokens + Clone> ToTokens for TokenizableOption<T> {
     }
 }
 
-#[derive(Clone, Eq, PartialEq, Hash)]
-pub struct TokenizableResult<T: ToTokens + Clone, E: ToTokens + Clone + std::error::Error>(
-    pub Result<T, E>,
-);
+pub struct TokenizableOption<'a, T, W>(pub Option<&'a T>, pub fn(&'a T) -> W);
 
-impl<T, E> Tokenizable for TokenizableResult<T, E>
-    where
-        T: ToTokens + Clone,
-        E: ToTokens + Clone + std::error::Error,
-{
-    type ValueType = Result<T, E>;
+impl<'a, T, W: ToTokens> TokenizableOption<'a, T, W> {
+    pub fn new(value: Option<&'a T>, quote: fn(&'a T) -> W) -> Self {
+        TokenizableOption(value, quote)
+    }
+}
 
+impl<'a, T, W: ToTokens> Tokenizable for TokenizableOption<'a, T, W> {
     fn value_token_stream(&self) -> TokenStream {
-        match &self.0 {
-            Ok(v) => quote::quote! {
-                Ok(#v)
+        let span = current_span();
+        match self.0.map(self.1) {
+            Some(nested) => quote::quote_spanned! {span=>
+                Some(#nested)
             },
-            Err(e) => quote::quote! {
-                Err(#e)
+            None => quote::quote_spanned! {span=>
+                None
             },
         }
     }
 
-    fn from_value(value: Self::ValueType) -> Self {
-        TokenizableResult(value)
-    }
-
     fn convert_token_stream(
         ty: &Type,
         value_path: &TokenStream,
     ) -> Result<Option<TokenStream>, Error> {
-        if let Type::Path(type_path) = ty {
-            let last_segment = type_path.path.segments.last().unwrap();
-
-            if last_segment.ident != "Result" {
-                return Ok(None)
-            }
+        let nested_type = match nested_type(ty, "Option")? {
+            Some(nested_type) => nested_type,
+            None => return Ok(None)
+        };
+        let quote = wrapper_closure(nested_type, "option_value")?;
+
+        Ok(Some(quote::quote! {
+            quote_data::TokenizableOption::new((#value_path).as_ref(), #quote)
+        }))
+    }
+}
 
-            let arguments = assert_angle_args(&last_segment.arguments)?;
-            let nested_types = get_nested_types(arguments);
+impl<'a, T, W: ToTokens> ToTokens for TokenizableOption<'a, T, W> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let value = self.value_token_stream();
+        (quote::quote! {
+                #value
+        }).to_tokens(tokens)
+    }
+}
 
-            let first_param = nested_types.first().ok_or_else(|| {
-                QuoteItError::TypeParamCountError("Result", 2, 0).into_syn_error(ty)
-            })?;
+pub struct TokenizableResult<'a, T, E, W, X>(
+    pub Result<&'a T, &'a E>,
+    pub fn(&'a T) -> W,
+    pub fn(&'a E) -> X,
+);
 
-            let second_param = nested_types.get(1).ok_or_else(|| {
-                QuoteItError::TypeParamCountError("Result", 2, 1).into_syn_error(ty)
-            })?;
+impl<'a, T, E, W: ToTokens, X: ToTokens> TokenizableResult<'a, T, E, W, X> {
+    pub fn new(value: Result<&'a T, &'a E>, quote: fn(&'a T) -> W, quote_error: fn(&'a E) -> X) -> Self {
+        TokenizableResult(value, quote, quote_error)
+    }
+}
 
-            let first_wrapped_value = get_value_wrapper(
-                first_param,
-                quote::quote! {
-                result
+impl<'a, T, E, W, X> Tokenizable for TokenizableResult<'a, T, E, W, X>
+    where
+        W: ToTokens,
+        X: ToTokens,
+{
+    fn value_token_stream(&self) -> TokenStream {
+        let span = current_span();
+        match self.0 {
+            Ok(v) => {
+                let v = (self.1)(v);
+                quote::quote_spanned! {span=>
+                    Ok(#v)
+                }
             },
-                false,
-                true,
-            )?;
-
-            let second_wrapped_value = get_value_wrapper(
-                second_param,
-                quote::quote! {
-                error
+            Err(e) => {
+                let e = (self.2)(e);
+                quote::quote_spanned! {span=>
+                    Err(#e)
+                }
             },
-                false,
-                true,
-            )?;
-
-            Ok(Some(quote::quote! {
-                quote_data::TokenizableResult::from_value(
-                    #value_path.clone()
-                    .map(|result| #first_wrapped_value)
-                    .map_err(|error| #second_wrapped_value)
-                )
-            }))
-        } else {
-            Ok(None)
         }
     }
+
+    fn convert_token_stream(
+        ty: &Type,
+        value_path: &TokenStream,
+    ) -> Result<Option<TokenStream>, Error> {
+        let (first_param, second_param) = match nested_type_pair(ty, "Result")? {
+            Some(params) => params,
+            None => return Ok(None)
+        };
+
+        let first_wrapped_value = wrapper_closure(first_param, "result")?;
+        let second_wrapped_value = wrapper_closure(second_param, "error")?;
+
+        Ok(Some(quote::quote! {
+            quote_data::TokenizableResult::new(
+                (#value_path).as_ref(),
+                #first_wrapped_value,
+                #second_wrapped_value
+            )
+        }))
+    }
 }
 
-impl<T, E> ToTokens for TokenizableResult<T, E>
+impl<'a, T, E, W, X> ToTokens for TokenizableResult<'a, T, E, W, X>
     where
-        T: ToTokens + Clone,
-        E: ToTokens + Clone + std::error::Error,
+        W: ToTokens,
+        X: ToTokens,
 {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let value = self.value_token_stream();
@@ -330,95 +768,72 @@ impl<T, E> ToTokens for TokenizableResult<T, E>
     }
 }
 
-#[derive(Clone)]
-pub struct TokenizableHashMap<K: Eq + Hash + Clone + ToTokens, V: Clone + ToTokens>(
-    pub HashMap<K, V>,
+pub struct TokenizableHashMap<'a, K, V, W, X, S = std::collections::hash_map::RandomState>(
+    pub &'a HashMap<K, V, S>,
+    pub fn(&'a K) -> W,
+    pub fn(&'a V) -> X,
 );
 
-impl<K, V> Tokenizable for TokenizableHashMap<K, V>
+impl<'a, K, V, W, X, S> TokenizableHashMap<'a, K, V, W, X, S>
     where
-        K: Eq + Hash + Clone + ToTokens,
-        V: Clone + ToTokens,
+        W: ToTokens,
+        X: ToTokens,
 {
-    type ValueType = Vec<(K, V)>;
+    pub fn new(value: &'a HashMap<K, V, S>, quote_key: fn(&'a K) -> W, quote_value: fn(&'a V) -> X) -> Self {
+        TokenizableHashMap(value, quote_key, quote_value)
+    }
+}
 
+impl<'a, K, V, W, X, S> Tokenizable for TokenizableHashMap<'a, K, V, W, X, S>
+    where
+        W: ToTokens,
+        X: ToTokens,
+{
     fn value_token_stream(&self) -> TokenStream {
+        let span = current_span();
         let pairs: Vec<TokenStream> = self
             .0
             .iter()
             .map(|(key, value)| {
-                quote::quote! {
+                let key = (self.1)(key);
+                let value = (self.2)(value);
+                quote::quote_spanned! {span=>
                     (#key, #value)
                 }
             })
             .collect();
 
-        quote::quote! {
+        quote::quote_spanned! {span=>
             vec![#(#pairs),*].into_iter().collect()
         }
     }
 
-    fn from_value(value: Self::ValueType) -> Self {
-        TokenizableHashMap(value.into_iter().collect())
-    }
-
     fn convert_token_stream(
         ty: &Type,
         value_path: &TokenStream,
     ) -> Result<Option<TokenStream>, Error> {
-        if let Type::Path(type_path) = ty {
-            let last_segment = type_path.path.segments.last().unwrap();
-
-            if last_segment.ident != "HashMap" {
-                return Ok(None)
-            }
-
-            let arguments = assert_angle_args(&last_segment.arguments)?;
-            let nested_types = get_nested_types(arguments);
-
-            let first_param = nested_types.first().ok_or_else(|| {
-                QuoteItError::TypeParamCountError("HashMap", 2, 0).into_syn_error(ty)
-            })?;
-
-            let second_param = nested_types.get(1).ok_or_else(|| {
-                QuoteItError::TypeParamCountError("HashMap", 2, 1).into_syn_error(ty)
-            })?;
-
-            let first_wrapped_value = get_value_wrapper(
-                first_param,
-                quote::quote! {
-                key
-            },
-                false,
-                true,
-            )?;
-
-            let second_wrapped_value = get_value_wrapper(
-                second_param,
-                quote::quote! {
-                value
-            },
-                false,
-                true,
-            )?;
-
-            Ok(Some(quote::quote! {
-                quote_data::TokenizableHashMap::from_value(
-                    #value_path.iter().map(
-                        |(key, value)| (#first_wrapped_value, #second_wrapped_value)
-                    ).collect()
-                )
-            }))
-        } else {
-            Ok(None)
-        }
+        let (first_param, second_param) = match nested_type_pair(ty, "HashMap")? {
+            Some(params) => params,
+            None => return Ok(None)
+        };
+
+        let first_wrapped_value = wrapper_closure(first_param, "key")?;
+        let second_wrapped_value = wrapper_closure(second_param, "value")?;
+
+        Ok(Some(quote::quote! {
+            quote_data::TokenizableHashMap::new(
+                #value_path,
+                #first_wrapped_value,
+                #second_wrapped_value
+            )
+        }))
     }
 }
 
-impl<K, V> ToTokens for TokenizableHashMap<K, V>
+impl<'a, K, V, W, X, S> ToTokens for TokenizableHashMap<'a, K, V, W, X, S>
     where
-        K: Eq + Hash + Clone + ToTokens,
-        V: Clone + ToTokens,
+        W: ToTokens,
+        X: ToTokens,
 {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let value = self.value_token_stream();
@@ -429,67 +844,43 @@ impl<K, V> ToTokens for TokenizableHashMap<K, V>
     }
 }
 
-#[derive(Clone)]
-pub struct TokenizableHashSet<T: ToTokens + Clone + Eq + Hash>(pub HashSet<T>);
+pub struct TokenizableHashSet<'a, T, W, S = std::collections::hash_map::RandomState>(
+    pub &'a HashSet<T, S>,
+    pub fn(&'a T) -> W,
+);
 
-impl<T> Tokenizable for TokenizableHashSet<T>
-    where
-        T: ToTokens + Clone + Eq + Hash,
-{
-    type ValueType = Vec<T>;
+impl<'a, T, W: ToTokens, S> TokenizableHashSet<'a, T, W, S> {
+    pub fn new(value: &'a HashSet<T, S>, quote: fn(&'a T) -> W) -> Self {
+        TokenizableHashSet(value, quote)
+    }
+}
 
+impl<'a, T, W: ToTokens, S> Tokenizable for TokenizableHashSet<'a, T, W, S> {
     fn value_token_stream(&self) -> TokenStream {
-        let items = self.0.iter();
-        quote::quote! {
+        let span = current_span();
+        let items = self.0.iter().map(self.1);
+        quote::quote_spanned! {span=>
             vec![#(#items),*].into_iter().collect()
         }
     }
 
-    fn from_value(value: Self::ValueType) -> Self {
-        TokenizableHashSet(value.into_iter().collect())
-    }
-
     fn convert_token_stream(
         ty: &Type,
         value_path: &TokenStream,
     ) -> Result<Option<TokenStream>, Error> {
-        if let Type::Path(type_path) = ty {
-            let last_segment = type_path.path.segments.last().unwrap();
-
-            if last_segment.ident != "HashSet" {
-                return Ok(None)
-            }
-
-            let arguments = assert_angle_args(&last_segment.arguments)?;
-            let nested_types = get_nested_types(arguments);
-            let nested_type = nested_types.first().ok_or_else(|| {
-                QuoteItError::TypeParamCountError("HashSet", 1, 0).into_syn_error(ty)
-            })?;
-
-            let wrapped_value = get_value_wrapper(
-                nested_type,
-                quote::quote! {
-                item
-            },
-                false,
-                true,
-            )?;
-
-            Ok(Some(quote::quote! {
-                quote_data::TokenizableHashSet::from_value(#value_path.iter().map(
-                    |item| #wrapped_value
-                ).collect())
-            }))
-        } else {
-            Ok(None)
-        }
+        let nested_type = match nested_type(ty, "HashSet")? {
+            Some(nested_type) => nested_type,
+            None => return Ok(None)
+        };
+        let quote = wrapper_closure(nested_type, "item")?;
+
+        Ok(Some(quote::quote! {
+            quote_data::TokenizableHashSet::new(#value_path, #quote)
+        }))
     }
 }
 
-impl<T> ToTokens for TokenizableHashSet<T>
-    where
-        T: ToTokens + Clone + Eq + Hash,
-{
+impl<'a, T, W: ToTokens, S> ToTokens for TokenizableHashSet<'a, T, W, S> {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let value = self.value_token_stream();
         (quote::quote! {
@@ -499,26 +890,27 @@ impl<T> ToTokens for TokenizableHashSet<T>
     }
 }
 
-#[derive(Clone)]
-pub struct TokenizablePair<A: ToTokens + Clone, B: ToTokens + Clone>(pub (A, B));
+pub struct TokenizablePair<A, B>(pub A, pub B);
 
-impl <A, B> Tokenizable for TokenizablePair<A, B>
-    where A: ToTokens + Clone, B: ToTokens + Clone {
-    type ValueType = (A, B);
+impl <A, B> TokenizablePair<A, B>
+    where A: ToTokens, B: ToTokens {
+    pub fn new(first: A, second: B) -> Self {
+        TokenizablePair(first, second)
+    }
+}
 
+impl <A, B> Tokenizable for TokenizablePair<A, B>
+    where A: ToTokens, B: ToTokens {
     fn value_token_stream(&self) -> TokenStream {
-        let first = &self.0.0;
-        let second = &self.0.1;
+        let span = current_span();
+        let first = &self.0;
+        let second = &self.1;
 
-        quote::quote! {
+        quote::quote_spanned! {span=>
             (#first, #second)
         }
     }
 
-    fn from_value(value: Self::ValueType) -> Self {
-        TokenizablePair(value)
-    }
-
     fn convert_token_stream(ty: &Type, value_path: &TokenStream) -> Result<Option<TokenStream>, Error> {
         if let Type::Tuple(type_tuple) = ty {
             let (first_ty, second_tye) = if type_tuple.elems.len() != 2 {
@@ -533,20 +925,18 @@ impl <A, B> Tokenizable for TokenizablePair<A, B>
 
             let first = get_value_wrapper(
                 first_ty,
-                quote::quote! {#value_path.0},
-                false,
+                quote::quote! {(#value_path).0},
                 true,
             )?;
 
             let second = get_value_wrapper(
                 second_tye,
-                quote::quote! {#value_path.1},
-                false,
+                quote::quote! {(#value_path).1},
                 true,
             )?;
 
             Ok(Some(quote::quote! {
-                quote_data::TokenizablePair::from_value((#first, #second))
+                quote_data::TokenizablePair::new(#first, #second)
             }))
         } else {
             Ok(None)
@@ -555,7 +945,7 @@ impl <A, B> Tokenizable for TokenizablePair<A, B>
 }
 
 impl <A, B> ToTokens for TokenizablePair<A, B>
-    where A: ToTokens + Clone, B: ToTokens + Clone ,
+    where A: ToTokens, B: ToTokens ,
 {
     fn to_tokens(&self, tokens: &mut TokenStream) {
         let value = self.value_token_stream();
@@ -566,22 +956,16 @@ impl <A, B> ToTokens for TokenizablePair<A, B>
     }
 }
 
-#[derive(Clone)]
 pub struct TokenizablePhantomData;
 
 impl Tokenizable for TokenizablePhantomData {
-    type ValueType = ();
-
     fn value_token_stream(&self) -> TokenStream {
-        quote::quote! {
+        let span = current_span();
+        quote::quote_spanned! {span=>
             std::marker::PhantomData::default()
         }
     }
 
-    fn from_value(_value: Self::ValueType) -> Self {
-        TokenizablePhantomData
-    }
-
     fn convert_token_stream(ty: &Type, _value_path: &TokenStream) -> Result<Option<TokenStream>, Error> {
         if let Type::Path(type_path) = ty {
             let last_segment = type_path.path.segments.last().unwrap();
@@ -591,7 +975,7 @@ impl Tokenizable for TokenizablePhantomData {
             }
 
             Ok(Some(quote::quote! {
-                quote_data::TokenizablePhantomData::from_value(())
+                quote_data::TokenizablePhantomData
             }))
         } else {
             Ok(None)
@@ -607,4 +991,349 @@ impl ToTokens for TokenizablePhantomData {
         })
             .to_tokens(tokens)
     }
-}
\ No newline at end of file
+}
+
+pub struct TokenizableRc<'a, T, W>(pub &'a Rc<T>, pub fn(&'a T) -> W);
+
+impl<'a, T, W: ToTokens> TokenizableRc<'a, T, W> {
+    pub fn new(value: &'a Rc<T>, quote: fn(&'a T) -> W) -> Self {
+        TokenizableRc(value, quote)
+    }
+}
+
+impl<'a, T, W: ToTokens> Tokenizable for TokenizableRc<'a, T, W> {
+    fn value_token_stream(&self) -> TokenStream {
+        let span = current_span();
+        let address = Rc::as_ptr(self.0) as *const () as usize;
+        let clone = quote::quote_spanned! {span=> ::std::rc::Rc::clone};
+        share_pointer(address, std::any::type_name::<Rc<T>>(), clone, || {
+            let value = (self.1)(self.0);
+            quote::quote_spanned! {span=>
+                ::std::rc::Rc::new(#value)
+            }
+        })
+    }
+
+    fn convert_token_stream(
+        ty: &Type,
+        value_path: &TokenStream,
+    ) -> Result<Option<TokenStream>, Error> {
+        let nested_type = match nested_type(ty, "Rc")? {
+            Some(nested_type) => nested_type,
+            None => return Ok(None)
+        };
+        let quote = wrapper_closure(nested_type, "value")?;
+
+        Ok(Some(quote::quote! {
+            quote_data::TokenizableRc::new(#value_path, #quote)
+        }))
+    }
+}
+
+impl<'a, T, W: ToTokens> ToTokens for TokenizableRc<'a, T, W> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let value = self.value_token_stream();
+        (quote::quote! {
+                #value
+        })
+            .to_tokens(tokens)
+    }
+}
+
+pub struct TokenizableArc<'a, T, W>(pub &'a Arc<T>, pub fn(&'a T) -> W);
+
+impl<'a, T, W: ToTokens> TokenizableArc<'a, T, W> {
+    pub fn new(value: &'a Arc<T>, quote: fn(&'a T) -> W) -> Self {
+        TokenizableArc(value, quote)
+    }
+}
+
+impl<'a, T, W: ToTokens> Tokenizable for TokenizableArc<'a, T, W> {
+    fn value_token_stream(&self) -> TokenStream {
+        let span = current_span();
+        let address = Arc::as_ptr(self.0) as *const () as usize;
+        let clone = quote::quote_spanned! {span=> ::std::sync::Arc::clone};
+        share_pointer(address, std::any::type_name::<Arc<T>>(), clone, || {
+            let value = (self.1)(self.0);
+            quote::quote_spanned! {span=>
+                ::std::sync::Arc::new(#value)
+            }
+        })
+    }
+
+    fn convert_token_stream(
+        ty: &Type,
+        value_path: &TokenStream,
+    ) -> Result<Option<TokenStream>, Error> {
+        let nested_type = match nested_type(ty, "Arc")? {
+            Some(nested_type) => nested_type,
+            None => return Ok(None)
+        };
+        let quote = wrapper_closure(nested_type, "value")?;
+
+        Ok(Some(quote::quote! {
+            quote_data::TokenizableArc::new(#value_path, #quote)
+        }))
+    }
+}
+
+impl<'a, T, W: ToTokens> ToTokens for TokenizableArc<'a, T, W> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let value = self.value_token_stream();
+        (quote::quote! {
+                #value
+        })
+            .to_tokens(tokens)
+    }
+}
+
+/// Borrows the `RefCell` when tokenized, so cycles through `Rc<RefCell<..>>` are reached lazily.
+pub struct TokenizableRefCell<'a, T>(pub &'a RefCell<T>, pub fn(&T) -> TokenStream);
+
+impl<'a, T> TokenizableRefCell<'a, T> {
+    pub fn new(value: &'a RefCell<T>, quote: fn(&T) -> TokenStream) -> Self {
+        TokenizableRefCell(value, quote)
+    }
+}
+
+impl<'a, T> Tokenizable for TokenizableRefCell<'a, T> {
+    fn value_token_stream(&self) -> TokenStream {
+        let span = current_span();
+        let value = (self.1)(&self.0.borrow());
+        quote::quote_spanned! {span=>
+            ::std::cell::RefCell::new(#value)
+        }
+    }
+
+    fn convert_token_stream(
+        ty: &Type,
+        value_path: &TokenStream,
+    ) -> Result<Option<TokenStream>, Error> {
+        let nested_type = match nested_type(ty, "RefCell")? {
+            Some(nested_type) => nested_type,
+            None => return Ok(None)
+        };
+        let wrapped_value = get_value_wrapper(
+            nested_type,
+            quote::quote! {
+                value
+            },
+            false,
+        )?;
+
+        Ok(Some(quote::quote! {
+            quote_data::TokenizableRefCell::new(
+                #value_path,
+                |value| quote::ToTokens::into_token_stream(#wrapped_value)
+            )
+        }))
+    }
+}
+
+impl<'a, T> ToTokens for TokenizableRefCell<'a, T> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let value = self.value_token_stream();
+        (quote::quote! {
+                #value
+        })
+            .to_tokens(tokens)
+    }
+}
+
+const PRIMITIVES: [&str; 16] = [
+    "i8", "i16", "i32", "i64", "i128", "isize", "u8", "u16", "u32", "u64", "u128", "usize",
+    "f32", "f64", "bool", "char",
+];
+
+/// Integers, floats, `bool` and `char`, which follow the [`LiteralPolicy`] and
+/// [`FloatPolicy`] of the context.
+pub trait Primitive: ToTokens {
+    fn unsuffixed_tokens(&self) -> TokenStream {
+        self.to_token_stream()
+    }
+
+    /// Name of the `core` constant of a NaN or infinite float, which has no literal.
+    fn non_finite(&self) -> Option<&'static str> {
+        None
+    }
+}
+
+macro_rules! primitive_unsuffixed {
+    ($($ty:ident => $unsuffixed:ident),*) => {
+        $(
+            impl Primitive for $ty {
+                fn unsuffixed_tokens(&self) -> TokenStream {
+                    Literal::$unsuffixed(*self).into_token_stream()
+                }
+            }
+        )*
+    };
+}
+
+primitive_unsuffixed!(
+    i8 => i8_unsuffixed, i16 => i16_unsuffixed, i32 => i32_unsuffixed, i64 => i64_unsuffixed,
+    i128 => i128_unsuffixed, isize => isize_unsuffixed, u8 => u8_unsuffixed,
+    u16 => u16_unsuffixed, u32 => u32_unsuffixed, u64 => u64_unsuffixed,
+    u128 => u128_unsuffixed, usize => usize_unsuffixed
+);
+
+macro_rules! float_unsuffixed {
+    ($($ty:ident => $unsuffixed:ident),*) => {
+        $(
+            impl Primitive for $ty {
+                fn unsuffixed_tokens(&self) -> TokenStream {
+                    Literal::$unsuffixed(*self).into_token_stream()
+                }
+
+                fn non_finite(&self) -> Option<&'static str> {
+                    non_finite_constant(*self as f64)
+                }
+            }
+        )*
+    };
+}
+
+float_unsuffixed!(f32 => f32_unsuffixed, f64 => f64_unsuffixed);
+
+impl Primitive for bool {}
+
+impl Primitive for char {}
+
+/// Tokenizes a primitive with the current span and literal policy.
+pub struct TokenizablePrimitive<'a, T>(pub &'a T);
+
+impl<'a, T: Primitive> TokenizablePrimitive<'a, T> {
+    pub fn new(value: &'a T) -> Self {
+        TokenizablePrimitive(value)
+    }
+}
+
+impl<'a, T: Primitive> Tokenizable for TokenizablePrimitive<'a, T> {
+    fn value_token_stream(&self) -> TokenStream {
+        if let Some(constant) = self.0.non_finite() {
+            let ty = format_ident!("{}", std::any::type_name::<T>());
+            let constant = format_ident!("{}", constant);
+            if let Err(error) = non_finite_allowed(&ty.to_string(), &constant.to_string()) {
+                report(error);
+            }
+
+            return quote::quote_spanned! {current_span()=>
+                ::core::#ty::#constant
+            };
+        }
+
+        let tokens = match QuoteContext::with(QuoteContext::literal_policy) {
+            LiteralPolicy::Suffixed => self.0.to_token_stream(),
+            LiteralPolicy::Unsuffixed => self.0.unsuffixed_tokens(),
+        };
+
+        respan(tokens, current_span())
+    }
+
+    fn convert_token_stream(
+        ty: &Type,
+        value_path: &TokenStream,
+    ) -> Result<Option<TokenStream>, Error> {
+        match PRIMITIVES.iter().any(|name| is_type(ty, name)) {
+            true => Ok(Some(quote::quote! {
+                quote_data::TokenizablePrimitive::new(#value_path)
+            })),
+            false => Ok(None)
+        }
+    }
+}
+
+impl<'a, T: Primitive> ToTokens for TokenizablePrimitive<'a, T> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let value = self.value_token_stream();
+        (quote::quote! {
+                #value
+        })
+            .to_tokens(tokens)
+    }
+}
+
+/// Tokenizes a `proc_macro2::Span` field; spans can't be rebuilt, so it becomes the call site.
+pub struct TokenizableSpan;
+
+impl Tokenizable for TokenizableSpan {
+    fn value_token_stream(&self) -> TokenStream {
+        let span = current_span();
+        quote::quote_spanned! {span=>
+            ::proc_macro2::Span::call_site()
+        }
+    }
+
+    /// Only `proc_macro2::Span` is handled, so user types named `Span` keep their own impls.
+    fn convert_token_stream(ty: &Type, _value_path: &TokenStream) -> Result<Option<TokenStream>, Error> {
+        match ty {
+            Type::Path(type_path)
+                if type_path.qself.is_none()
+                    && type_path.path.segments.len() == 2
+                    && type_path.path.segments[0].ident == "proc_macro2"
+                    && type_path.path.segments[1].ident == "Span" =>
+            {
+                Ok(Some(quote::quote! {
+                    quote_data::TokenizableSpan
+                }))
+            }
+            _ => Ok(None),
+        }
+    }
+}
+
+impl ToTokens for TokenizableSpan {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let value = self.value_token_stream();
+        (quote::quote! {
+                #value
+        })
+            .to_tokens(tokens)
+    }
+}
+
+/// Tokenizes a `Box<T>` as `Box::new(..)`, including trait objects made quotable with
+/// `DynQuote`.
+pub struct TokenizableBox<'a, T: ?Sized, W>(pub &'a T, pub fn(&'a T) -> W);
+
+impl<'a, T: ?Sized, W: ToTokens> TokenizableBox<'a, T, W> {
+    /// `value` is the content of the box.
+    pub fn new(value: &'a T, quote: fn(&'a T) -> W) -> Self {
+        TokenizableBox(value, quote)
+    }
+}
+
+impl<'a, T: ?Sized, W: ToTokens> Tokenizable for TokenizableBox<'a, T, W> {
+    fn value_token_stream(&self) -> TokenStream {
+        let span = current_span();
+        let value = (self.1)(self.0);
+        quote::quote_spanned! {span=>
+            ::std::boxed::Box::new(#value)
+        }
+    }
+
+    fn convert_token_stream(
+        ty: &Type,
+        value_path: &TokenStream,
+    ) -> Result<Option<TokenStream>, Error> {
+        let nested_type = match nested_type(ty, "Box")? {
+            Some(nested_type) => nested_type,
+            None => return Ok(None)
+        };
+        let quote = wrapper_closure(nested_type, "value")?;
+
+        Ok(Some(quote::quote! {
+            quote_data::TokenizableBox::new(&**(#value_path), #quote)
+        }))
+    }
+}
+
+impl<'a, T: ?Sized, W: ToTokens> ToTokens for TokenizableBox<'a, T, W> {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        let value = self.value_token_stream();
+        (quote::quote! {
+                #value
+        })
+            .to_tokens(tokens)
+    }
+}
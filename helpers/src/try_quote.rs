@@ -0,0 +1,224 @@
+#[cfg(feature = "to-token")]
+use proc_macro2::TokenStream;
+use std::cell::RefCell;
+use std::error::Error as StdError;
+use std::fmt::{self, Display, Write as FmtWrite};
+use crate::source::ToRustSource;
+
+thread_local! {
+    static TRY_QUOTE: RefCell<TryQuoteState> = RefCell::new(TryQuoteState::default());
+}
+
+#[derive(Default)]
+struct TryQuoteState {
+    /// Number of [`try_quote`] calls running, so failures are recorded instead of panicking.
+    depth: usize,
+    error: Option<QuoteError>,
+    path: Vec<PathSegment>,
+}
+
+#[derive(Clone, Debug, PartialEq, Eq)]
+enum PathSegment {
+    Field(String),
+    Index(usize),
+}
+
+/// A value that can't be represented as tokens, with the path of the field it was found at,
+/// e.g. `servers[3].addr`.
+#[derive(Clone, Debug, PartialEq, Eq)]
+pub struct QuoteError {
+    path: Vec<PathSegment>,
+    message: String,
+}
+
+impl QuoteError {
+    pub fn new(message: impl Display) -> Self {
+        QuoteError {
+            path: TRY_QUOTE.with(|state| state.borrow().path.clone()),
+            message: message.to_string(),
+        }
+    }
+
+    /// Path of the failing field relative to the quoted value, empty for the value itself.
+    pub fn path(&self) -> String {
+        let mut path = String::new();
+        for segment in self.path.iter() {
+            match segment {
+                PathSegment::Field(name) if path.is_empty() => path.push_str(name),
+                PathSegment::Field(name) => {
+                    path.push('.');
+                    path.push_str(name);
+                }
+                PathSegment::Index(index) => path.push_str(&format!("[{}]", index)),
+            }
+        }
+
+        path
+    }
+
+    pub fn message(&self) -> &str {
+        &self.message
+    }
+}
+
+impl Display for QuoteError {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        match self.path() {
+            path if path.is_empty() => f.write_str(&self.message),
+            path => write!(f, "`{}`: {}", path, self.message),
+        }
+    }
+}
+
+impl StdError for QuoteError {}
+
+/// Tokenization that reports values that can't be quoted instead of panicking.
+///
+/// `QuoteIt` implements it for every type, and builds `ToTokens` on top of it, panicking with
+/// the error.
+#[cfg(feature = "to-token")]
+pub trait TryQuote {
+    fn try_to_tokens(&self) -> Result<TokenStream, QuoteError>;
+}
+
+/// Runs `f`, returning the first failure reported with [`fail`] while it runs.
+#[cfg(feature = "to-token")]
+pub fn try_quote(f: impl FnOnce() -> TokenStream) -> Result<TokenStream, QuoteError> {
+    match try_scope(f) {
+        (_, Some(error)) => Err(error),
+        (tokens, None) => Ok(tokens),
+    }
+}
+
+/// Writes the source of `value` into `out`, returning the first failure reported with
+/// [`fail_source`] or [`fail`] while it's written, with the path of the failing field.
+pub fn try_write_source<T: ToRustSource + ?Sized>(
+    value: &T,
+    out: &mut dyn FmtWrite,
+) -> Result<(), QuoteError> {
+    match try_scope(|| value.write_source(out)) {
+        (_, Some(error)) => Err(error),
+        (Ok(()), None) => Ok(()),
+        (Err(_), None) => Err(QuoteError::new("a formatting trait implementation returned an error")),
+    }
+}
+
+/// Runs `f` in a try scope, in which failures are recorded instead of panicking, returning
+/// its result and the first failure.
+fn try_scope<R>(f: impl FnOnce() -> R) -> (R, Option<QuoteError>) {
+    let _guard = TRY_QUOTE.with(|state| {
+        let mut state = state.borrow_mut();
+        state.depth += 1;
+        DepthGuard(state.error.take())
+    });
+    let result = f();
+    let error = TRY_QUOTE.with(|state| state.borrow_mut().error.take());
+
+    (result, error)
+}
+
+/// Leaves a try scope when dropped, restoring the failure of the enclosing one, even if `f`
+/// panics.
+struct DepthGuard(Option<QuoteError>);
+
+impl Drop for DepthGuard {
+    fn drop(&mut self) {
+        TRY_QUOTE.with(|state| {
+            let mut state = state.borrow_mut();
+            state.depth -= 1;
+            state.error = self.0.take();
+        });
+    }
+}
+
+/// Reports that the value being tokenized at the current field path can't be quoted.
+///
+/// Inside [`try_quote`] the first failure is returned from it and empty tokens are returned in
+/// place of the value; otherwise this panics.
+#[cfg(feature = "to-token")]
+pub fn fail(message: impl Display) -> TokenStream {
+    quote_or_fail(Err(QuoteError::new(message)))
+}
+
+/// The tokens of `result`, or empty tokens after reporting its error like [`fail`].
+#[cfg(feature = "to-token")]
+pub fn quote_or_fail(result: Result<TokenStream, QuoteError>) -> TokenStream {
+    match result {
+        Ok(tokens) => tokens,
+        Err(error) => {
+            report(error);
+            TokenStream::new()
+        }
+    }
+}
+
+/// Like [`fail`] for `ToRustSource`, returning the `fmt::Error` that stops the write.
+///
+/// The failure is recorded instead of panicking, so `write_source` returns the error; outside
+/// of a try scope only the latest failure is kept, and `to_rust_source` panics with it.
+pub fn fail_source(message: impl Display) -> fmt::Error {
+    record_source(QuoteError::new(message))
+}
+
+/// Records `error` as the failure that stops a `write_source`, returning the `fmt::Error`.
+pub(crate) fn record_source(error: QuoteError) -> fmt::Error {
+    TRY_QUOTE.with(|state| {
+        let mut state = state.borrow_mut();
+        if state.depth == 0 {
+            state.error = Some(error);
+        } else {
+            state.error.get_or_insert(error);
+        }
+    });
+
+    fmt::Error
+}
+
+/// Takes the failure recorded by the last `write_source` that failed outside of a try scope.
+pub(crate) fn take_source_error() -> Option<QuoteError> {
+    TRY_QUOTE.with(|state| {
+        let mut state = state.borrow_mut();
+        match state.depth {
+            0 => state.error.take(),
+            _ => None,
+        }
+    })
+}
+
+/// Records `error` as the failure of the running `try_quote`, or panics outside of one.
+#[cfg(feature = "to-token")]
+pub(crate) fn report(error: QuoteError) {
+    TRY_QUOTE.with(|state| {
+        let mut state = state.borrow_mut();
+        if state.depth == 0 {
+            drop(state);
+            panic!("{}", error);
+        }
+        state.error.get_or_insert(error);
+    })
+}
+
+/// Runs `f` with `name` appended to the field path of failures.
+pub fn in_field<R>(name: &str, f: impl FnOnce() -> R) -> R {
+    in_segment(PathSegment::Field(name.to_string()), f)
+}
+
+/// Runs `f` with the index `index` appended to the field path of failures.
+pub fn in_index<R>(index: usize, f: impl FnOnce() -> R) -> R {
+    in_segment(PathSegment::Index(index), f)
+}
+
+fn in_segment<R>(segment: PathSegment, f: impl FnOnce() -> R) -> R {
+    TRY_QUOTE.with(|state| state.borrow_mut().path.push(segment));
+    let _guard = SegmentGuard;
+    f()
+}
+
+/// Pops the innermost segment of the field path when dropped, even if the scope panics.
+struct SegmentGuard;
+
+impl Drop for SegmentGuard {
+    fn drop(&mut self) {
+        TRY_QUOTE.with(|state| state.borrow_mut().path.pop());
+    }
+}
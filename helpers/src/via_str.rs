@@ -0,0 +1,76 @@
+#[cfg(feature = "to-token")]
+use proc_macro2::TokenStream;
+#[cfg(feature = "to-token")]
+use quote::ToTokens;
+use std::fmt::{self, Display, Write as FmtWrite};
+use std::str::FromStr;
+use crate::source::ToRustSource;
+#[cfg(feature = "to-token")]
+use crate::span::{current_span, respan};
+#[cfg(feature = "to-token")]
+use crate::try_quote::report;
+use crate::try_quote::{record_source, QuoteError};
+
+/// Tokenizes a value as `<T as FromStr>::from_str("...").unwrap()` from its `Display` form,
+/// for `#[quote(via_str)]`. `ty` is the type `T` as seen from the generated code.
+pub struct TokenizableViaStr {
+    text: String,
+    ty: String,
+    /// The round-trip failure of a checked value, reported when it's quoted.
+    error: Option<QuoteError>,
+}
+
+impl TokenizableViaStr {
+    pub fn new<T: Display + ?Sized>(value: &T, ty: &str) -> Self {
+        TokenizableViaStr {
+            text: value.to_string(),
+            ty: ty.to_string(),
+            error: None,
+        }
+    }
+
+    /// Like `new`, but parses the text back while tokenizing, and fails naming `path` unless
+    /// it displays the same again.
+    pub fn checked<T: Display + FromStr>(value: &T, ty: &str, path: &str) -> Self {
+        let mut value = Self::new(value, ty);
+        value.error = match value.text.parse::<T>() {
+            Ok(parsed) if parsed.to_string() == value.text => None,
+            Ok(parsed) => Some(QuoteError::new(format!(
+                "`{}` doesn't round-trip through `FromStr`: {:?} is parsed as {:?}",
+                path, value.text, parsed.to_string()
+            ))),
+            Err(_) => Some(QuoteError::new(format!(
+                "`{}` doesn't round-trip through `FromStr`: {:?} can't be parsed",
+                path, value.text
+            ))),
+        };
+
+        value
+    }
+}
+
+#[cfg(feature = "to-token")]
+impl ToTokens for TokenizableViaStr {
+    fn to_tokens(&self, tokens: &mut TokenStream) {
+        if let Some(error) = &self.error {
+            report(error.clone());
+        }
+        let ty = TokenStream::from_str(&self.ty)
+            .unwrap_or_else(|e| panic!("invalid type `{}`: {}", self.ty, e));
+        let ty = respan(ty, current_span());
+        let text = &self.text;
+
+        (quote::quote_spanned! {current_span()=>
+            <#ty as ::core::str::FromStr>::from_str(#text).unwrap()
+        }).to_tokens(tokens)
+    }
+}
+
+impl ToRustSource for TokenizableViaStr {
+    fn write_source(&self, out: &mut dyn FmtWrite) -> fmt::Result {
+        if let Some(error) = &self.error {
+            return Err(record_source(error.clone()));
+        }
+        write!(out, "<{} as ::core::str::FromStr>::from_str({:?}).unwrap()", self.ty, self.text)
+    }
+}
@@ -1,3 +1,51 @@
 pub use helpers::*;
 
-pub use derive::QuoteIt;
+pub use derive::{quoted_module, QuoteIt};
+
+/// A [`NamedFn`] of the function at `path`, e.g. `named_fn!(crate::handlers::run)`.
+#[macro_export]
+macro_rules! named_fn {
+    ($path:path) => {
+        $crate::NamedFn::new($path as _, stringify!($path))
+    };
+}
+
+/// Implements `ToTokens` and `ToRustSource` for trait object types whose trait has the
+/// [`DynQuote`] supertrait, e.g. `dyn_quote!(dyn Rule, dyn Rule + Send)`.
+#[macro_export]
+macro_rules! dyn_quote {
+    ($($object:ty),+ $(,)?) => {
+        $(
+            $crate::__to_tokens_impl! {
+                impl quote::ToTokens for $object {
+                    fn to_tokens(&self, tokens: &mut proc_macro2::TokenStream) {
+                        $crate::DynQuote::dyn_to_tokens(self, tokens)
+                    }
+                }
+            }
+
+            impl $crate::ToRustSource for $object {
+                fn write_source(&self, out: &mut dyn ::core::fmt::Write) -> ::core::fmt::Result {
+                    $crate::DynQuote::dyn_write_source(self, out)
+                }
+            }
+        )+
+    };
+}
+
+/// Wraps the `quote::ToTokens` impls emitted by `QuoteIt`, so they only exist with `to-token`.
+#[cfg(feature = "to-token")]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __to_tokens_impl {
+    ($($item:tt)*) => {
+        $($item)*
+    };
+}
+
+#[cfg(not(feature = "to-token"))]
+#[doc(hidden)]
+#[macro_export]
+macro_rules! __to_tokens_impl {
+    ($($item:tt)*) => {};
+}
@@ -0,0 +1,34 @@
+use quote::ToTokens;
+use quote_data::{QuoteIt, ToRustSource};
+
+#[derive(QuoteIt)]
+#[quote(auto_path)]
+struct Root(u8);
+
+mod shapes {
+    use quote_data::QuoteIt;
+
+    #[derive(QuoteIt)]
+    #[quote(auto_path = "geometry")]
+    pub enum Shape {
+        Dot,
+        Circle { radius: u8 },
+    }
+}
+
+fn strip(text: String) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+#[test]
+fn test_auto_path() {
+    assert_eq!(strip(Root(1).to_token_stream().to_string()), "crate::Root::new(1u8)");
+    assert_eq!(
+        strip(shapes::Shape::Circle { radius: 2 }.to_token_stream().to_string()),
+        "geometry::shapes::Shape::Circle{radius:2u8}"
+    );
+
+    let mut source = String::new();
+    shapes::Shape::Dot.write_source(&mut source).unwrap();
+    assert_eq!(source, "geometry::shapes::Shape::Dot");
+}
@@ -0,0 +1,105 @@
+use quote_data::{EmitConfig, QuoteIt, ToRustSource};
+use quote::ToTokens;
+use std::fs;
+
+#[derive(QuoteIt)]
+struct Asset {
+    name: String,
+    data: Vec<u8>,
+    header: &'static [u8],
+    text: &'static str,
+}
+
+fn get_result<T: ToTokens>(value: T) -> String {
+    value
+        .to_token_stream()
+        .to_string()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect()
+}
+
+fn asset() -> Asset {
+    Asset {
+        name: "logo".to_string(),
+        data: vec![0, b'a', b'"', 255, 7],
+        header: b"PNG\n",
+        text: "long text",
+    }
+}
+
+#[test]
+fn test_byte_string() {
+    assert_eq!(
+        get_result(asset()),
+        "Asset::new(\"logo\".to_string(),b\"\\0a\\\"\\xFF\\x07\".to_vec(),b\"PNG\\n\",\"longtext\")"
+    );
+    assert_eq!(
+        asset().to_rust_source(),
+        "Asset::new(\"logo\".to_string(), b\"\\0a\\\"\\xFF\\x07\".to_vec(), b\"PNG\\n\", \"long text\")"
+    );
+}
+
+#[test]
+fn test_side_files() {
+    let dir = std::env::temp_dir().join("quote_data_test_side_files");
+    let _ = fs::remove_dir_all(&dir);
+    let config = EmitConfig::new().side_files(&dir, 4);
+
+    let tokens = config.clone().scope(|| get_result(asset()));
+    let source = config.scope(|| asset().to_rust_source());
+
+    let mut files: Vec<String> = fs::read_dir(&dir)
+        .unwrap()
+        .map(|entry| entry.unwrap().path().to_string_lossy().into_owned())
+        .collect();
+    files.sort();
+
+    assert_eq!(files.len(), 2);
+    let text = files.iter().find(|path| path.ends_with(".txt")).unwrap();
+    let bin = files.iter().find(|path| path.ends_with(".bin")).unwrap();
+    assert_eq!(fs::read_to_string(text).unwrap(), "long text");
+    assert_eq!(fs::read(bin).unwrap(), vec![0, b'a', b'"', 255, 7]);
+
+    assert!(tokens.starts_with("Asset::new(\"logo\".to_string(),include_bytes!("));
+    assert!(tokens.contains(".to_vec(),b\"PNG\\n\",include_str!("));
+    assert!(source.contains(&format!("include_str!({:?})", text)));
+    assert!(source.contains(&format!("include_bytes!({:?}).to_vec()", bin)));
+}
+
+#[test]
+fn test_side_file_names() {
+    let dir = std::env::temp_dir().join("quote_data_test_side_file_names");
+    let _ = fs::remove_dir_all(&dir);
+    fs::create_dir_all(&dir).unwrap();
+    // A different blob already using the name the text hashes to.
+    let taken = dir.join("blob_7faced76e61a928a.txt");
+    fs::write(&taken, "other text").unwrap();
+
+    let source = EmitConfig::new().side_files(&dir, 4).scope(|| asset().to_rust_source());
+
+    assert_eq!(fs::read_to_string(&taken).unwrap(), "other text");
+    let text = dir.join("blob_7faced76e61a928a_1.txt");
+    assert_eq!(fs::read_to_string(&text).unwrap(), "long text");
+    assert!(source.contains(&format!("include_str!({:?})", text.to_string_lossy())));
+}
+
+#[test]
+fn test_side_files_in_out_dir() {
+    let out_dir = std::env::temp_dir().join("quote_data_test_out_dir");
+    let _ = fs::remove_dir_all(&out_dir);
+    fs::create_dir_all(&out_dir).unwrap();
+    std::env::set_var("OUT_DIR", &out_dir);
+
+    let config = EmitConfig::new().side_files(out_dir.join("blobs"), 4);
+    let tokens = config.clone().scope(|| get_result(asset()));
+    let source = config.scope(|| asset().to_rust_source());
+
+    let bin = "include_bytes!(concat!(env!(\"OUT_DIR\"),\"/blobs/blob_49e843c69bf62216.bin\")).to_vec()";
+    assert!(tokens.contains(bin));
+    assert!(get_result_source(source).contains(bin));
+}
+
+fn get_result_source(source: String) -> String {
+    source.chars().filter(|c| !c.is_whitespace()).collect()
+}
@@ -0,0 +1,135 @@
+use quote_data::{EmitConfig, FloatPolicy, QuoteContext, QuoteIt, ToRustSource, TryQuote};
+use quote::ToTokens;
+
+#[derive(QuoteIt)]
+struct Item(u8);
+
+#[derive(QuoteIt)]
+struct Table {
+    #[quote(chunk = 2)]
+    ids: Vec<u32>,
+    #[quote(chunk = 2, chunk_ty = "Item")]
+    items: Vec<Item>,
+    names: Vec<String>,
+}
+
+mod model {
+    use quote_data::QuoteIt;
+
+    #[derive(QuoteIt)]
+    #[mod_path = "crate::model"]
+    pub struct Row(pub u8);
+}
+
+#[derive(QuoteIt)]
+struct Rows {
+    #[quote(chunk = 1, chunk_ty = "crate::model::Row")]
+    rows: Vec<model::Row>,
+}
+
+#[derive(QuoteIt)]
+struct Weights {
+    #[quote(chunk = 4)]
+    values: Vec<f64>,
+}
+
+fn strip(source: String) -> String {
+    source.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn table() -> Table {
+    Table {
+        ids: vec![1, 2, 3],
+        items: vec![Item(1), Item(2), Item(3)],
+        names: vec!["a".to_string(), "b".to_string(), "c".to_string()],
+    }
+}
+
+#[test]
+fn test_chunk_attribute() {
+    let tokens = table().to_token_stream();
+
+    assert_eq!(
+        strip(tokens.to_string()),
+        strip(
+            "Table::new(\
+                {\
+                    let mut chunked = ::std::vec::Vec::with_capacity(3usize);\
+                    chunked.extend(vec![1u32, 2u32]);\
+                    chunked.extend(vec![3u32]);\
+                    chunked\
+                },\
+                {\
+                    fn __quote_data_chunk_0() -> ::std::vec::Vec<Item> { vec![Item::new(1u8), Item::new(2u8)] }\
+                    fn __quote_data_chunk_1() -> ::std::vec::Vec<Item> { vec![Item::new(3u8)] }\
+                    let mut chunked = ::std::vec::Vec::with_capacity(3usize);\
+                    chunked.extend(__quote_data_chunk_0());\
+                    chunked.extend(__quote_data_chunk_1());\
+                    chunked\
+                },\
+                vec![\"a\".to_string(), \"b\".to_string(), \"c\".to_string()]\
+            )"
+            .to_string()
+        )
+    );
+    assert_eq!(strip(table().to_rust_source()), strip(tokens.to_string()));
+}
+
+#[test]
+fn test_chunk_config() {
+    let config = EmitConfig::new().chunks(2);
+    let tokens = config.clone().scope(|| table().to_token_stream());
+    let source = config.scope(|| table().to_rust_source());
+
+    assert!(strip(tokens.to_string()).ends_with(
+        "{letmutchunked=::std::vec::Vec::with_capacity(3usize);\
+         chunked.extend(vec![\"a\".to_string(),\"b\".to_string()]);\
+         chunked.extend(vec![\"c\".to_string()]);\
+         chunked})"
+    ));
+    assert_eq!(strip(source), strip(tokens.to_string()));
+}
+
+#[test]
+fn test_config_scope_panic() {
+    let result = std::panic::catch_unwind(|| {
+        EmitConfig::new().chunks(2).scope(|| panic!("emit failed"))
+    });
+    assert!(result.is_err());
+
+    assert!(strip(table().to_rust_source())
+        .ends_with("vec![\"a\".to_string(),\"b\".to_string(),\"c\".to_string()])"));
+}
+
+#[test]
+fn test_chunk_error_path() {
+    let weights = Weights {
+        values: vec![1.0, f64::NAN],
+    };
+    let strict = QuoteContext::new().floats(FloatPolicy::Strict);
+
+    let error = strict.clone().scope(|| weights.try_to_tokens()).unwrap_err();
+    assert_eq!(error.path(), "values[1]");
+    let error = strict.scope(|| weights.try_to_rust_source()).unwrap_err();
+    assert_eq!(error.path(), "values[1]");
+}
+
+#[test]
+fn test_chunk_ty_source() {
+    let rows = Rows {
+        rows: vec![model::Row(1), model::Row(2)],
+    };
+
+    assert_eq!(
+        rows.to_rust_source(),
+        "Rows::new({ \
+            fn __quote_data_chunk_0() -> ::std::vec::Vec<crate::model::Row> { vec![crate::model::Row::new(1u8)] } \
+            fn __quote_data_chunk_1() -> ::std::vec::Vec<crate::model::Row> { vec![crate::model::Row::new(2u8)] } \
+            let mut chunked = ::std::vec::Vec::with_capacity(2usize); \
+            chunked.extend(__quote_data_chunk_0()); \
+            chunked.extend(__quote_data_chunk_1()); \
+            chunked \
+        })"
+    );
+    assert_eq!(strip(rows.to_rust_source()), strip(rows.to_token_stream().to_string()));
+}
@@ -0,0 +1,95 @@
+use quote::ToTokens;
+use quote_data::{LiteralPolicy, QuoteContext, QuoteIt, ToRustSource, ToTokensWith};
+
+#[derive(QuoteIt)]
+#[mod_path = "crate::model"]
+struct Point {
+    x: i32,
+    y: f64,
+}
+
+#[derive(QuoteIt)]
+#[mod_path = "crate::model::shape"]
+enum Shape {
+    Dot(Point),
+    Line { from: Point, to: Point },
+}
+
+fn strip(text: String) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn shape() -> Shape {
+    Shape::Line {
+        from: Point { x: 0, y: 0.5 },
+        to: Point { x: 1, y: 1.0 },
+    }
+}
+
+#[test]
+fn test_default_context() {
+    assert_eq!(
+        strip(shape().to_token_stream().to_string()),
+        "crate::model::shape::Shape::Line{from:crate::model::Point::new(0i32,0.5f64),to:crate::model::Point::new(1i32,1f64)}"
+    );
+}
+
+#[test]
+fn test_path_rewrites() {
+    let context = QuoteContext::new()
+        .path("crate::model", "my_models::model")
+        .path("crate::model::shape", "shapes");
+
+    assert_eq!(
+        strip(shape().to_token_stream_with(&context).to_string()),
+        "shapes::Shape::Line{from:my_models::model::Point::new(0i32,0.5f64),to:my_models::model::Point::new(1i32,1f64)}"
+    );
+
+    let context = QuoteContext::new().path("crate::model", "");
+    assert_eq!(
+        strip(Point { x: 2, y: 3.0 }.to_token_stream_with(&context).to_string()),
+        "Point::new(2i32,3f64)"
+    );
+}
+
+#[test]
+fn test_literal_policy() {
+    let context = QuoteContext::new().literals(LiteralPolicy::Unsuffixed);
+    let expected = "crate::model::shape::Shape::Dot(crate::model::Point::new(3,1.5))";
+    let shape = Shape::Dot(Point { x: 3, y: 1.5 });
+
+    assert_eq!(strip(shape.to_token_stream_with(&context).to_string()), expected);
+
+    let source = context.scope(|| {
+        let mut source = String::new();
+        shape.write_source(&mut source).unwrap();
+        source
+    });
+    assert_eq!(strip(source), expected);
+}
+
+#[test]
+fn test_scope_panic() {
+    let context = QuoteContext::new().literals(LiteralPolicy::Unsuffixed);
+    let result = std::panic::catch_unwind(|| context.scope(|| panic!("quoting failed")));
+    assert!(result.is_err());
+
+    assert_eq!(strip(Point { x: 2, y: 3.5 }.to_rust_source()), "crate::model::Point::new(2i32,3.5f64)");
+}
+
+#[test]
+fn test_float_text() {
+    let context = QuoteContext::new().literals(LiteralPolicy::Unsuffixed);
+    for value in [1.0, 0.5, -2.0, 1e300, 1.5e-7] {
+        assert_eq!(
+            value.to_rust_source(),
+            proc_macro2::Literal::f64_suffixed(value).to_string()
+        );
+        assert_eq!(
+            context.clone().scope(|| value.to_rust_source()),
+            proc_macro2::Literal::f64_unsuffixed(value).to_string()
+        );
+    }
+    assert_eq!(1.0f32.to_rust_source(), "1f32");
+    assert_eq!(context.scope(|| 1.0f32.to_rust_source()), "1.0");
+}
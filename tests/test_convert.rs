@@ -0,0 +1,124 @@
+use quote::ToTokens;
+use quote_data::{QuoteIt, ToRustSource};
+
+#[derive(QuoteIt)]
+struct Table {
+    transitions: Vec<(u8, u8)>,
+}
+
+/// Compiled state machine, quoted as its transition table.
+#[derive(QuoteIt)]
+#[quote(into = "Table")]
+struct Machine {
+    edges: std::collections::BTreeMap<u8, u8>,
+}
+
+impl From<&Machine> for Table {
+    fn from(machine: &Machine) -> Self {
+        Table {
+            transitions: machine.edges.iter().map(|(from, to)| (*from, *to)).collect(),
+        }
+    }
+}
+
+#[derive(QuoteIt)]
+#[quote(path = crate::machine, from = "Table")]
+struct Rebuilt {
+    edges: std::collections::BTreeMap<u8, u8>,
+}
+
+impl From<&Rebuilt> for Table {
+    fn from(machine: &Rebuilt) -> Self {
+        Table {
+            transitions: machine.edges.iter().map(|(from, to)| (*from, *to)).collect(),
+        }
+    }
+}
+
+#[derive(QuoteIt)]
+#[quote(into = "String")]
+enum Level {
+    Low,
+    High,
+}
+
+impl From<&Level> for String {
+    fn from(level: &Level) -> Self {
+        match level {
+            Level::Low => "low".to_string(),
+            Level::High => "high".to_string(),
+        }
+    }
+}
+
+struct Flags(Vec<bool>);
+
+impl From<&Flags> for u32 {
+    fn from(flags: &Flags) -> Self {
+        flags.0.iter().rev().fold(0, |bits, flag| bits << 1 | *flag as u32)
+    }
+}
+
+impl From<u32> for Flags {
+    fn from(bits: u32) -> Self {
+        Flags((0..4).map(|index| bits >> index & 1 == 1).collect())
+    }
+}
+
+#[derive(QuoteIt)]
+struct Config {
+    #[quote(into = "u32")]
+    flags: Flags,
+    #[quote(from = "u32")]
+    mask: Flags,
+    level: Level,
+}
+
+fn strip(text: String) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn assert_quoted<T: ToTokens + ToRustSource>(value: &T, expected: &str) {
+    assert_eq!(strip(value.to_token_stream().to_string()), expected);
+
+    let mut source = String::new();
+    value.write_source(&mut source).unwrap();
+    assert_eq!(strip(source), expected);
+}
+
+#[test]
+fn test_container_into() {
+    let machine = Machine {
+        edges: [(1, 2), (2, 1)].iter().copied().collect(),
+    };
+
+    assert_quoted(&machine, "Table::new(vec![(1u8,2u8),(2u8,1u8)])");
+    assert_quoted(&Level::High, "\"high\".to_string()");
+}
+
+#[test]
+fn test_container_from() {
+    let machine = Rebuilt {
+        edges: [(1, 2)].iter().copied().collect(),
+    };
+
+    assert_quoted(
+        &machine,
+        "<crate::machine::Rebuiltas::core::convert::From<Table>>::from(Table::new(vec![(1u8,2u8)]))",
+    );
+}
+
+#[test]
+fn test_field_conversion() {
+    let config = Config {
+        flags: Flags(vec![true, false, true]),
+        mask: Flags(vec![false, true]),
+        level: Level::Low,
+    };
+
+    assert_quoted(
+        &config,
+        "Config::new(5u32,<Flagsas::core::convert::From<u32>>::from(2u32),\"low\".to_string())",
+    );
+    assert_eq!(Flags::from(5).0, vec![true, false, true, false]);
+}
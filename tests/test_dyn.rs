@@ -0,0 +1,67 @@
+use quote::ToTokens;
+use quote_data::{dyn_quote, DynQuote, QuoteIt, ToRustSource};
+
+trait Rule: DynQuote {
+    fn check(&self, value: u32) -> bool;
+}
+
+dyn_quote!(dyn Rule, dyn Rule + Send);
+
+#[derive(QuoteIt)]
+struct Max(u32);
+
+impl Rule for Max {
+    fn check(&self, value: u32) -> bool {
+        value <= self.0
+    }
+}
+
+#[derive(QuoteIt)]
+struct Even;
+
+impl Rule for Even {
+    fn check(&self, value: u32) -> bool {
+        value.is_multiple_of(2)
+    }
+}
+
+#[derive(QuoteIt)]
+struct Registry {
+    rules: Vec<Box<dyn Rule>>,
+    fallback: Box<dyn Rule + Send>,
+    limit: Box<u32>,
+}
+
+fn strip(text: String) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn assert_quoted<T: ToTokens + ToRustSource + ?Sized>(value: &T, expected: &str) {
+    assert_eq!(strip(value.to_token_stream().to_string()), expected);
+
+    let mut source = String::new();
+    value.write_source(&mut source).unwrap();
+    assert_eq!(strip(source), expected);
+}
+
+#[test]
+fn test_dyn() {
+    let registry = Registry {
+        rules: vec![Box::new(Max(10)), Box::new(Even)],
+        fallback: Box::new(Max(1)),
+        limit: Box::new(3),
+    };
+
+    assert!(registry.rules.iter().all(|rule| rule.check(4)));
+    assert!(!registry.fallback.check(2));
+    assert_quoted(
+        &registry,
+        "Registry::new(\
+         vec![::std::boxed::Box::new(Max::new(10u32)),::std::boxed::Box::new(Even::new())],\
+         ::std::boxed::Box::new(Max::new(1u32)),\
+         ::std::boxed::Box::new(3u32))",
+    );
+
+    let rule: &dyn Rule = &Even;
+    assert_quoted(rule, "Even::new()");
+}
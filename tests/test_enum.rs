@@ -59,12 +59,12 @@ pub fn test_enum() {
 #[test]
 pub fn test_enum_with_unnamed_field() {
     let a = get_string(Test3::<u8>::A(1, 1, "test".to_string(), vec![1, 2, 3]));
-    assert_eq!(a, "Test3::A(1u8,1u16,\"test\".to_string(),vec![1u8,2u8,3u8])");
+    assert_eq!(a, "Test3::A(1u8,1u16,\"test\".to_string(),b\"\\x01\\x02\\x03\".to_vec())");
     let b = get_string(Test3::<u8>::B{
         a: 1, b: 1, c: "test".to_string(),
         d: vec![1, 2, 3]
     });
-    assert_eq!(b, "Test3::B{a:1u8,b:1u16,c:\"test\".to_string(),d:vec![1u8,2u8,3u8]}");
+    assert_eq!(b, "Test3::B{a:1u8,b:1u16,c:\"test\".to_string(),d:b\"\\x01\\x02\\x03\".to_vec()}");
     let c = get_string(Test3::<u8>::C(PhantomData::default()));
     assert_eq!(c, "Test3::C(std::marker::PhantomData::default())");
 }
\ No newline at end of file
@@ -0,0 +1,76 @@
+use quote::ToTokens;
+use quote_data::{QuoteIt, ToRustSource};
+
+#[derive(QuoteIt)]
+#[allow(dead_code)]
+struct Credentials {
+    user: String,
+    #[quote(env = "API_KEY")]
+    api_key: String,
+    #[quote(env = "API_REGION")]
+    region: Option<String>,
+    #[quote(redact)]
+    password: String,
+    #[quote(redact = "default")]
+    token: Option<String>,
+}
+
+#[derive(QuoteIt)]
+#[allow(dead_code)]
+enum Auth {
+    Key(#[quote(env = "API_KEY")] String),
+    Basic {
+        user: String,
+        #[quote(redact)]
+        password: String,
+    },
+}
+
+fn strip(text: String) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn assert_quoted<T: ToTokens + ToRustSource>(value: &T, expected: &str) {
+    assert_eq!(strip(value.to_token_stream().to_string()), expected);
+
+    let mut source = String::new();
+    value.write_source(&mut source).unwrap();
+    assert_eq!(strip(source), expected);
+}
+
+#[test]
+fn test_env() {
+    let credentials = Credentials {
+        user: "admin".to_string(),
+        api_key: "secret-key".to_string(),
+        region: Some("eu".to_string()),
+        password: "secret-password".to_string(),
+        token: Some("secret-token".to_string()),
+    };
+
+    assert_quoted(
+        &credentials,
+        "Credentials::new(\"admin\".to_string(),\
+         ::core::env!(\"API_KEY\").to_string(),\
+         ::core::option_env!(\"API_REGION\").map(::std::string::String::from),\
+         ::core::compile_error!(\"`Credentials.password`isredactedandcan'tbequoted\"),\
+         ::core::default::Default::default())",
+    );
+    assert!(!credentials.to_token_stream().to_string().contains("secret"));
+}
+
+#[test]
+fn test_env_enum() {
+    assert_quoted(
+        &Auth::Key("secret".to_string()),
+        "Auth::Key(::core::env!(\"API_KEY\").to_string())",
+    );
+    assert_quoted(
+        &Auth::Basic {
+            user: "admin".to_string(),
+            password: "secret".to_string(),
+        },
+        "Auth::Basic{user:\"admin\".to_string(),\
+         password:::core::compile_error!(\"`Auth::Basic.password`isredactedandcan'tbequoted\")}",
+    );
+}
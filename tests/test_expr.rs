@@ -0,0 +1,72 @@
+use quote::ToTokens;
+use quote_data::{QuoteIt, ToRustSource};
+use std::time::Instant;
+
+/// Derived from the payload, so it is recomputed instead of quoted.
+struct Checksum(u32);
+
+impl Checksum {
+    fn of(payload: &[u8]) -> Self {
+        Checksum(payload.iter().map(|byte| *byte as u32).sum())
+    }
+}
+
+#[derive(QuoteIt)]
+#[allow(dead_code)]
+struct Packet {
+    #[quote(expr = "Checksum::of(&#payload)")]
+    checksum: Checksum,
+    payload: Vec<u8>,
+    #[quote(expr = "::std::time::Instant::now()")]
+    created: Instant,
+}
+
+#[derive(QuoteIt)]
+#[allow(dead_code)]
+enum Event {
+    Tick {
+        at: u64,
+        #[quote(expr = "Instant::now()")]
+        seen: Instant,
+    },
+    Resize(u16, #[quote(expr = "(#0 as u32) * 2")] u32),
+}
+
+fn strip(text: String) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn assert_quoted<T: ToTokens + ToRustSource>(value: &T, expected: &str) {
+    assert_eq!(strip(value.to_token_stream().to_string()), expected);
+
+    let mut source = String::new();
+    value.write_source(&mut source).unwrap();
+    assert_eq!(strip(source), expected);
+}
+
+#[test]
+fn test_struct_expr() {
+    let payload = vec![1, 2];
+    let packet = Packet {
+        checksum: Checksum::of(&payload),
+        payload,
+        created: Instant::now(),
+    };
+
+    assert_eq!(packet.checksum.0, 3);
+    assert_quoted(
+        &packet,
+        "Packet::new(Checksum::of(&b\"\\x01\\x02\".to_vec()),b\"\\x01\\x02\".to_vec(),::std::time::Instant::now())",
+    );
+}
+
+#[test]
+fn test_enum_expr() {
+    let tick = Event::Tick {
+        at: 3,
+        seen: Instant::now(),
+    };
+
+    assert_quoted(&tick, "Event::Tick{at:3u64,seen:Instant::now()}");
+    assert_quoted(&Event::Resize(2, 4), "Event::Resize(2u16,(2u16asu32)*2)");
+}
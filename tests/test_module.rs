@@ -0,0 +1,89 @@
+use quote::ToTokens;
+
+#[quote_data::quoted_module(path = "crate::schema")]
+mod schema {
+    pub struct User {
+        pub id: u32,
+        pub role: Role,
+    }
+
+    pub enum Role {
+        Admin,
+        Member { team: String },
+    }
+
+    #[quote(path = crate::shared)]
+    pub struct Overridden;
+
+    #[derive(Debug, quote_data::QuoteIt)]
+    pub struct AlreadyDerived(pub u8);
+
+    #[quote(target = "crate::runtime::Limits")]
+    pub struct Limits {
+        pub max: u32,
+    }
+
+    #[quote(transparent)]
+    pub struct Id(pub u32);
+
+    #[quote(from = "u8")]
+    pub struct Level(pub u8);
+
+    impl From<&Level> for u8 {
+        fn from(level: &Level) -> Self {
+            level.0
+        }
+    }
+
+    pub mod audit {
+        pub struct Entry(pub u8);
+    }
+}
+
+fn strip(text: String) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+#[test]
+fn test_quoted_module() {
+    let user = schema::User {
+        id: 1,
+        role: schema::Role::Member {
+            team: "core".to_string(),
+        },
+    };
+
+    assert_eq!(
+        strip(user.to_token_stream().to_string()),
+        "crate::schema::User::new(1u32,crate::schema::Role::Member{team:\"core\".to_string()})"
+    );
+    assert_eq!(
+        strip(schema::Role::Admin.to_token_stream().to_string()),
+        "crate::schema::Role::Admin"
+    );
+    assert_eq!(
+        strip(schema::Overridden.to_token_stream().to_string()),
+        "crate::shared::Overridden::new()"
+    );
+    assert_eq!(
+        strip(schema::AlreadyDerived(2).to_token_stream().to_string()),
+        "crate::schema::AlreadyDerived::new(2u8)"
+    );
+    assert_eq!(
+        strip(schema::audit::Entry(3).to_token_stream().to_string()),
+        "crate::schema::audit::Entry::new(3u8)"
+    );
+}
+
+#[test]
+fn test_quoted_module_overrides() {
+    assert_eq!(
+        strip(schema::Limits { max: 4 }.to_token_stream().to_string()),
+        "crate::runtime::Limits{max:4u32}"
+    );
+    assert_eq!(strip(schema::Id(5).to_token_stream().to_string()), "5u32");
+    assert_eq!(
+        strip(schema::Level(6).to_token_stream().to_string()),
+        "<crate::schema::Levelas::core::convert::From<u8>>::from(6u8)"
+    );
+}
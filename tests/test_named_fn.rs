@@ -0,0 +1,97 @@
+use quote::ToTokens;
+use quote_data::{named_fn, NamedFn, QuoteContext, QuoteIt, ToRustSource, ToTokensWith};
+
+pub struct Ctx {
+    hits: u32,
+}
+
+mod model {
+    use super::Ctx;
+
+    pub fn run(ctx: &mut Ctx) {
+        ctx.hits += 2;
+    }
+}
+
+mod handlers {
+    use super::Ctx;
+
+    pub fn ping(ctx: &mut Ctx) {
+        ctx.hits += 1;
+    }
+
+    pub fn reset(ctx: &mut Ctx) {
+        ctx.hits = 0;
+    }
+}
+
+#[derive(QuoteIt)]
+struct Handler {
+    name: String,
+    run: NamedFn<fn(&mut Ctx)>,
+}
+
+#[derive(QuoteIt)]
+#[allow(dead_code)]
+struct Fallback {
+    #[quote(fn_path = "crate::handlers::reset")]
+    run: fn(&mut Ctx),
+}
+
+fn strip(text: String) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn assert_quoted<T: ToTokens + ToRustSource>(value: &T, expected: &str) {
+    assert_eq!(strip(value.to_token_stream().to_string()), expected);
+
+    let mut source = String::new();
+    value.write_source(&mut source).unwrap();
+    assert_eq!(strip(source), expected);
+}
+
+#[test]
+fn test_named_fn() {
+    let handler = Handler {
+        name: "ping".to_string(),
+        run: named_fn!(handlers::ping),
+    };
+    let mut ctx = Ctx { hits: 0 };
+    (handler.run)(&mut ctx);
+
+    assert_eq!(ctx.hits, 1);
+    assert_quoted(
+        &handler,
+        "Handler::new(\"ping\".to_string(),quote_data::NamedFn::new(handlers::pingas_,\"handlers::ping\"))",
+    );
+}
+
+#[test]
+fn test_fn_path() {
+    let fallback = Fallback { run: handlers::reset };
+
+    assert_quoted(&fallback, "Fallback::new(crate::handlers::reset)");
+
+    let context = QuoteContext::new().path("crate", "my_app");
+    assert_eq!(
+        strip(fallback.to_token_stream_with(&context).to_string()),
+        "Fallback::new(my_app::handlers::reset)"
+    );
+}
+
+#[test]
+fn test_named_fn_rewrite_once() {
+    let handler = Handler {
+        name: "run".to_string(),
+        run: named_fn!(crate::model::run),
+    };
+    assert_eq!(handler.run.path(), "crate::model::run");
+
+    let context = QuoteContext::new().path("crate::model", "crate::model::v2");
+    let expected = "Handler::new(\"run\".to_string(),\
+                    quote_data::NamedFn::new(crate::model::v2::runas_,\"crate::model::v2::run\"))";
+    assert_eq!(strip(handler.to_token_stream_with(&context).to_string()), expected);
+
+    let source = context.scope(|| handler.to_rust_source());
+    assert_eq!(strip(source), expected);
+}
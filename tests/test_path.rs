@@ -0,0 +1,53 @@
+use quote::ToTokens;
+use quote_data::QuoteIt;
+
+#[derive(QuoteIt)]
+#[quote(path = crate::model)]
+struct Model(u8);
+
+#[derive(QuoteIt)]
+#[quote(path = ::external::types)]
+enum Kind {
+    A,
+}
+
+#[derive(QuoteIt)]
+#[quote(path = super)]
+struct Parent;
+
+#[derive(QuoteIt)]
+#[quote(path = "crate::quoted")]
+struct Quoted;
+
+#[derive(QuoteIt)]
+#[mod_path = "self::legacy"]
+struct Legacy;
+
+macro_rules! quoted_struct {
+    ($name:ident) => {
+        #[derive(QuoteIt)]
+        #[quote(path = $crate::generated)]
+        struct $name;
+    };
+}
+
+quoted_struct!(Generated);
+
+fn get_result<T: ToTokens>(value: T) -> String {
+    value
+        .to_token_stream()
+        .to_string()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect()
+}
+
+#[test]
+fn test_path_attribute() {
+    assert_eq!(get_result(Model(1)), "crate::model::Model::new(1u8)");
+    assert_eq!(get_result(Kind::A), "::external::types::Kind::A");
+    assert_eq!(get_result(Parent), "super::Parent::new()");
+    assert_eq!(get_result(Quoted), "crate::quoted::Quoted::new()");
+    assert_eq!(get_result(Legacy), "self::legacy::Legacy::new()");
+    assert_eq!(get_result(Generated), "crate::generated::Generated::new()");
+}
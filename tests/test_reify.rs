@@ -0,0 +1,71 @@
+use proc_macro2::{Ident, Literal, Span, TokenStream};
+use quote::ToTokens;
+use quote_data::{QuoteIt, Reified, ToRustSource};
+use syn::parse_quote;
+
+#[derive(QuoteIt)]
+struct Field {
+    #[quote(reify)]
+    name: Ident,
+    #[quote(reify)]
+    ty: syn::Type,
+    #[quote(reify)]
+    default: Option<Literal>,
+    #[quote(reify)]
+    body: TokenStream,
+    attrs: Vec<Reified<syn::Attribute>>,
+}
+
+fn get_result<T: ToTokens>(value: T) -> String {
+    value
+        .to_token_stream()
+        .to_string()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect()
+}
+
+fn field() -> Field {
+    Field {
+        name: Ident::new("count", Span::call_site()),
+        ty: parse_quote!(Vec<u8>),
+        default: Some(Literal::u8_suffixed(1)),
+        body: quote::quote! {self.count + 1},
+        attrs: vec![Reified(parse_quote!(#[doc = "count"]))],
+    }
+}
+
+#[test]
+fn test_reify_field() {
+    assert_eq!(
+        get_result(field()),
+        concat!(
+            "Field::new(",
+            "::proc_macro2::Ident::new(\"count\",::proc_macro2::Span::call_site()),",
+            "::syn::parse_quote!{Vec<u8>},",
+            "Some(<::proc_macro2::Literalas::core::str::FromStr>::from_str(\"1u8\").unwrap()),",
+            "::quote::quote!{self.count+1},",
+            "vec![quote_data::Reified(::syn::parse2(",
+            "<::proc_macro2::TokenStreamas::core::str::FromStr>::from_str(\"#[doc=\\\"count\\\"]\").unwrap()",
+            ").unwrap())])"
+        )
+    );
+}
+
+#[test]
+fn test_reify_raw_ident() {
+    let ident = Ident::new_raw("type", Span::call_site());
+    assert_eq!(
+        get_result(quote_data::TokenizableReified::new(&ident)),
+        "::proc_macro2::Ident::new_raw(\"type\",::proc_macro2::Span::call_site())"
+    );
+}
+
+#[test]
+fn test_reify_source() {
+    let mut source = String::new();
+    field().write_source(&mut source).unwrap();
+    let source: String = source.chars().filter(|c| !c.is_whitespace()).collect();
+
+    assert_eq!(source, get_result(field()));
+}
@@ -0,0 +1,96 @@
+use quote::ToTokens;
+use quote_data::{QuoteIt, ToRustSource};
+
+mod vendor {
+    pub struct Point {
+        pub x: i32,
+        pub y: i32,
+    }
+
+    pub enum Method {
+        Get,
+        Custom(String),
+        Weighted { name: String, weight: u8 },
+    }
+}
+
+#[derive(QuoteIt)]
+#[quote(remote = "vendor::Point")]
+#[allow(dead_code)]
+struct PointDef {
+    x: i32,
+    y: i32,
+}
+
+#[derive(QuoteIt)]
+#[quote(remote = "vendor::Method")]
+#[allow(dead_code)]
+enum MethodDef {
+    Get,
+    Custom(String),
+    Weighted { name: String, weight: u8 },
+}
+
+#[derive(QuoteIt)]
+#[quote(remote = "std::ops::Range")]
+#[allow(dead_code)]
+struct RangeDef<Idx: ToTokens> {
+    start: Idx,
+    end: Idx,
+}
+
+#[derive(QuoteIt)]
+struct Route {
+    #[quote(with = "MethodDef::quote_remote")]
+    method: vendor::Method,
+    #[quote(with = "PointDef::quote_remote")]
+    origin: vendor::Point,
+    #[quote(with = "RangeDef::quote_remote")]
+    ports: std::ops::Range<u16>,
+}
+
+fn strip(text: String) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn route() -> Route {
+    Route {
+        method: vendor::Method::Weighted {
+            name: "post".to_string(),
+            weight: 2,
+        },
+        origin: vendor::Point { x: 1, y: -2 },
+        ports: 80..443,
+    }
+}
+
+#[test]
+fn test_remote() {
+    assert_eq!(
+        strip(PointDef::quote_remote(&vendor::Point { x: 3, y: 4 }).to_string()),
+        "vendor::Point{x:3i32,y:4i32}"
+    );
+    assert_eq!(
+        strip(MethodDef::quote_remote(&vendor::Method::Custom("head".to_string())).to_string()),
+        "vendor::Method::Custom(\"head\".to_string())"
+    );
+    assert_eq!(
+        strip(MethodDef::quote_remote(&vendor::Method::Get).to_string()),
+        "vendor::Method::Get"
+    );
+}
+
+#[test]
+fn test_with() {
+    let expected = concat!(
+        "Route::new(",
+        "vendor::Method::Weighted{name:\"post\".to_string(),weight:2u8},",
+        "vendor::Point{x:1i32,y:-2i32},",
+        "std::ops::Range{start:80u16,end:443u16})"
+    );
+    assert_eq!(strip(route().to_token_stream().to_string()), expected);
+
+    let mut source = String::new();
+    route().write_source(&mut source).unwrap();
+    assert_eq!(strip(source), expected);
+}
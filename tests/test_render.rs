@@ -0,0 +1,116 @@
+use quote_data::render::pretty;
+use quote_data::QuoteIt;
+use quote::{quote, ToTokens};
+use proc_macro2::{Delimiter, Group, TokenStream, TokenTree};
+use std::str::FromStr;
+
+#[derive(QuoteIt)]
+#[mod_path = "test"]
+struct Config {
+    name: String,
+    servers: Vec<String>,
+    ports: Option<Vec<u16>>,
+}
+
+fn assert_round_trip(tokens: &TokenStream) {
+    let rendered = pretty(tokens);
+    let parsed = TokenStream::from_str(rendered.as_str()).unwrap();
+
+    assert_eq!(token_trees(&parsed), token_trees(tokens));
+}
+
+/// Every token of `tokens` with the groups' delimiters, ignoring `Punct` spacing, which only
+/// depends on the whitespace.
+fn token_trees(tokens: &TokenStream) -> Vec<String> {
+    tokens
+        .clone()
+        .into_iter()
+        .flat_map(|token| match token {
+            TokenTree::Group(group) => {
+                let mut trees = vec![format!("{:?}", group.delimiter())];
+                trees.extend(token_trees(&group.stream()));
+                trees.push("end".to_string());
+                trees
+            }
+            TokenTree::Punct(punct) => vec![punct.as_char().to_string()],
+            token => vec![token.to_string()],
+        })
+        .collect()
+}
+
+#[test]
+fn test_short_expression() {
+    let config = Config {
+        name: "iroha".to_string(),
+        servers: vec!["yui".to_string()],
+        ports: None,
+    };
+
+    assert_eq!(
+        pretty(&config.to_token_stream()),
+        "test::Config::new(\"iroha\".to_string(), vec![\"yui\".to_string()], None)"
+    );
+}
+
+#[test]
+fn test_long_argument_list() {
+    let config = Config {
+        name: "iroha".to_string(),
+        servers: vec!["yukino".to_string(), "yui".to_string(), "iroha".to_string()],
+        ports: Some(vec![8080, 8081, 8082]),
+    };
+    let tokens = config.to_token_stream();
+
+    assert_eq!(
+        pretty(&tokens),
+        "test::Config::new(\n    \"iroha\".to_string(),\n    vec![\"yukino\".to_string(), \"yui\".to_string(), \"iroha\".to_string()],\n    Some(vec![8080u16, 8081u16, 8082u16])\n)"
+    );
+    assert_round_trip(&tokens);
+}
+
+#[test]
+fn test_items() {
+    let tokens = quote! {
+        #[allow(dead_code)]
+        pub static CONFIG: &[u8] = &[1u8, 2u8];
+        impl<T: Clone> Foo<T> where T: 'static {
+            pub fn get(&self) -> Vec<T> {
+                let value = -1i32;
+                match self { Foo::A(x) => x.clone(), _ => vec![] }
+            }
+        }
+    };
+
+    assert_eq!(
+        pretty(&tokens),
+        "#[allow(dead_code)]\npub static CONFIG: &[u8] = &[1u8, 2u8];\nimpl<T: Clone> Foo<T> where T: 'static {\n    pub fn get(&self) -> Vec<T> {\n        let value = -1i32;\n        match self {\n            Foo::A(x) => x.clone(),\n            _ => vec![]\n        }\n    }\n}"
+    );
+    assert_round_trip(&tokens);
+}
+
+#[test]
+fn test_lifetimes() {
+    let tokens = quote! {
+        fn get<'a, T: 'a>(x: &'a mut T, y: &'static str) -> &'a T {
+            'outer: loop { break 'outer; }
+            x
+        }
+    };
+
+    assert_eq!(
+        pretty(&tokens),
+        "fn get<'a, T: 'a>(x: &'a mut T, y: &'static str) -> &'a T {\n    'outer: loop {\n        break 'outer;\n    }\n    x\n}"
+    );
+    assert_round_trip(&tokens);
+}
+
+#[test]
+fn test_invisible_groups() {
+    let sum = Group::new(Delimiter::None, quote! {1 + 1});
+    let single = Group::new(Delimiter::None, quote! {x});
+    let tokens = quote! {#sum * 2 + #single};
+    let rendered = pretty(&tokens);
+
+    assert_eq!(rendered, "(1 + 1) * 2 + x");
+    assert_round_trip(&TokenStream::from_str(&rendered).unwrap());
+}
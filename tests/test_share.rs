@@ -0,0 +1,221 @@
+use quote_data::{quote_shared, shared_rust_source, QuoteIt, SharedValueError, ToRustSource};
+use quote::ToTokens;
+use std::cell::RefCell;
+use std::rc::Rc;
+use std::sync::Arc;
+
+#[derive(QuoteIt, Clone)]
+struct User {
+    name: String,
+    role: String,
+}
+
+#[derive(QuoteIt)]
+struct Team {
+    lead: Rc<User>,
+    members: Vec<Rc<User>>,
+    owner: Arc<String>,
+    backup: User,
+}
+
+#[derive(QuoteIt)]
+struct Roster {
+    lead: Rc<User>,
+    #[quote(chunk = 1, chunk_ty = "Rc<User>")]
+    members: Vec<Rc<User>>,
+    backup: Rc<User>,
+}
+
+#[derive(QuoteIt)]
+struct Node {
+    value: u8,
+    next: RefCell<Option<Rc<Node>>>,
+}
+
+fn strip(tokens: String) -> String {
+    tokens.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+#[test]
+fn test_shared_values() {
+    let lead = Rc::new(User {
+        name: "yukinoshita yukino".to_string(),
+        role: "administrator".to_string(),
+    });
+    let team = Team {
+        lead: lead.clone(),
+        members: vec![lead.clone(), lead],
+        owner: Arc::new("yukinoshita yukino".to_string()),
+        backup: User {
+            name: "yuigahama yui".to_string(),
+            role: "administrator".to_string(),
+        },
+    };
+
+    assert_eq!(
+        strip(quote_shared(&team).unwrap().to_string()),
+        strip(
+            "{\
+                let __quote_data_shared_0 = \"yukinoshita yukino\".to_string();\
+                let __quote_data_shared_1 = \"administrator\".to_string();\
+                let __quote_data_shared_3 = ::std::rc::Rc::new(User::new(\
+                    __quote_data_shared_0.clone(), __quote_data_shared_1.clone()\
+                ));\
+                Team::new(\
+                    ::std::rc::Rc::clone(&__quote_data_shared_3),\
+                    vec![\
+                        ::std::rc::Rc::clone(&__quote_data_shared_3),\
+                        ::std::rc::Rc::clone(&__quote_data_shared_3)\
+                    ],\
+                    ::std::sync::Arc::new(__quote_data_shared_0.clone()),\
+                    User::new(\"yuigahama yui\".to_string(), __quote_data_shared_1.clone())\
+                )\
+            }"
+            .to_string()
+        )
+    );
+    assert_eq!(
+        strip(team.to_token_stream().to_string()),
+        strip(
+            "Team::new(\
+                ::std::rc::Rc::new(User::new(\"yukinoshita yukino\".to_string(), \"administrator\".to_string())),\
+                vec![\
+                    ::std::rc::Rc::new(User::new(\"yukinoshita yukino\".to_string(), \"administrator\".to_string())),\
+                    ::std::rc::Rc::new(User::new(\"yukinoshita yukino\".to_string(), \"administrator\".to_string()))\
+                ],\
+                ::std::sync::Arc::new(\"yukinoshita yukino\".to_string()),\
+                User::new(\"yuigahama yui\".to_string(), \"administrator\".to_string())\
+            )"
+            .to_string()
+        )
+    );
+}
+
+#[test]
+fn test_shared_source() {
+    let lead = Rc::new(User {
+        name: "yukinoshita yukino".to_string(),
+        role: "administrator".to_string(),
+    });
+    let team = Team {
+        lead: lead.clone(),
+        members: vec![lead.clone(), lead],
+        owner: Arc::new("yukinoshita yukino".to_string()),
+        backup: User {
+            name: "yuigahama yui".to_string(),
+            role: "administrator".to_string(),
+        },
+    };
+
+    // Only the shared `Rc` is hoisted; repeated strings are written again.
+    assert_eq!(
+        shared_rust_source(&team).unwrap(),
+        "{ \
+            let __quote_data_shared_0 = ::std::rc::Rc::new(User::new(\
+                \"yukinoshita yukino\".to_string(), \"administrator\".to_string()\
+            )); \
+            Team::new(\
+                ::std::rc::Rc::clone(&__quote_data_shared_0), \
+                vec![\
+                    ::std::rc::Rc::clone(&__quote_data_shared_0), \
+                    ::std::rc::Rc::clone(&__quote_data_shared_0)\
+                ], \
+                ::std::sync::Arc::new(\"yukinoshita yukino\".to_string()), \
+                User::new(\"yuigahama yui\".to_string(), \"administrator\".to_string())\
+            ) \
+        }"
+    );
+    assert_eq!(
+        strip(team.to_rust_source()),
+        strip(team.to_token_stream().to_string())
+    );
+}
+
+#[test]
+fn test_shared_chunk_helpers() {
+    let lead = Rc::new(User {
+        name: "yukinoshita yukino".to_string(),
+        role: "administrator".to_string(),
+    });
+    let roster = Roster {
+        lead: lead.clone(),
+        members: vec![lead.clone(), lead.clone()],
+        backup: lead,
+    };
+
+    // The chunk helpers can't see the hoisted bindings, so their items are quoted in full.
+    let user = "::std::rc::Rc::new(User::new(\"yukinoshita yukino\".to_string(), \"administrator\".to_string()))";
+    let members = format!(
+        "{{\
+            fn __quote_data_chunk_0() -> ::std::vec::Vec<Rc<User>> {{ vec![{user}] }}\
+            fn __quote_data_chunk_1() -> ::std::vec::Vec<Rc<User>> {{ vec![{user}] }}\
+            let mut chunked = ::std::vec::Vec::with_capacity(2usize);\
+            chunked.extend(__quote_data_chunk_0());\
+            chunked.extend(__quote_data_chunk_1());\
+            chunked\
+        }}"
+    );
+    assert_eq!(
+        strip(quote_shared(&roster).unwrap().to_string()),
+        strip(format!(
+            "{{\
+                let __quote_data_shared_3 = {user};\
+                Roster::new(\
+                    ::std::rc::Rc::clone(&__quote_data_shared_3),\
+                    {members},\
+                    ::std::rc::Rc::clone(&__quote_data_shared_3)\
+                )\
+            }}"
+        ))
+    );
+    assert_eq!(
+        strip(shared_rust_source(&roster).unwrap()),
+        strip(format!(
+            "{{\
+                let __quote_data_shared_0 = {user};\
+                Roster::new(\
+                    ::std::rc::Rc::clone(&__quote_data_shared_0),\
+                    {members},\
+                    ::std::rc::Rc::clone(&__quote_data_shared_0)\
+                )\
+            }}"
+        ))
+    );
+}
+
+fn cycle() -> Rc<Node> {
+    let first = Rc::new(Node {
+        value: 1,
+        next: RefCell::new(None),
+    });
+    let second = Rc::new(Node {
+        value: 2,
+        next: RefCell::new(Some(first.clone())),
+    });
+    *first.next.borrow_mut() = Some(second);
+
+    first
+}
+
+#[test]
+fn test_cycle_error() {
+    let first = cycle();
+    let error = quote_shared(&*first).unwrap_err();
+    assert_eq!(error, SharedValueError::Cycle(std::any::type_name::<Rc<Node>>()));
+    let error = shared_rust_source(&*first).unwrap_err();
+    assert_eq!(error, SharedValueError::Cycle(std::any::type_name::<Rc<Node>>()));
+
+    *first.next.borrow_mut() = None;
+}
+
+#[test]
+#[should_panic(expected = "reference cycle")]
+fn test_cycle_panic() {
+    let _ = cycle().to_token_stream();
+}
+
+#[test]
+#[should_panic(expected = "reference cycle")]
+fn test_cycle_panic_source() {
+    let _ = cycle().to_rust_source();
+}
@@ -0,0 +1,79 @@
+use helpers::TokenizableError;
+use quote_data::{write_rust_source, QuoteIt, ToRustSource};
+use quote::ToTokens;
+use std::collections::HashMap;
+use std::marker::PhantomData;
+
+#[derive(QuoteIt)]
+struct TestTuple(i32, i64, Vec<u8>);
+
+#[derive(QuoteIt)]
+#[mod_path = "test"]
+struct TestStruct<T> {
+    basic: i32,
+    string: String,
+    option_string: Option<String>,
+    result: Result<String, TokenizableError>,
+    map: HashMap<usize, String>,
+    str: &'static str,
+    pair: (String, f64),
+    _marker: PhantomData<T>,
+}
+
+#[derive(QuoteIt)]
+#[mod_path = "test"]
+enum TestEnum<T> {
+    A,
+    B(u8, char, Vec<String>),
+    C { a: bool, b: PhantomData<T> },
+}
+
+fn strip(source: String) -> String {
+    source.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn assert_same_output<T: ToTokens + ToRustSource>(value: T) {
+    assert_eq!(
+        strip(value.to_rust_source()),
+        strip(value.to_token_stream().to_string())
+    );
+}
+
+#[test]
+fn test_struct_source() {
+    let tuple = TestTuple(1, -1, vec![1, 2, 3]);
+    assert_eq!(tuple.to_rust_source(), "TestTuple::new(1i32, -1i64, b\"\\x01\\x02\\x03\".to_vec())");
+    assert_same_output(tuple);
+
+    assert_same_output(TestStruct::<u8> {
+        basic: 0,
+        string: "iroha \"yui\"".to_string(),
+        option_string: Some("iroha".to_string()),
+        result: Ok("233".to_string()),
+        map: vec![(1, "yukino".to_string())].into_iter().collect(),
+        str: "test",
+        pair: ("114".to_string(), 5.14),
+        _marker: PhantomData,
+    });
+}
+
+#[test]
+fn test_enum_source() {
+    assert_eq!(TestEnum::<u8>::A.to_rust_source(), "test::TestEnum::A");
+    assert_eq!(
+        TestEnum::<u8>::C { a: true, b: PhantomData }.to_rust_source(),
+        "test::TestEnum::C { a: true, b: std::marker::PhantomData::default() }"
+    );
+    assert_same_output(TestEnum::<u8>::B(1, 'x', vec!["a".to_string()]));
+}
+
+#[test]
+fn test_write_io() {
+    let mut buffer: Vec<u8> = Vec::new();
+    write_rust_source(&TestEnum::<u8>::B(1, '\n', vec![]), &mut buffer).unwrap();
+
+    assert_eq!(
+        String::from_utf8(buffer).unwrap(),
+        "test::TestEnum::B(1u8, '\\n', vec![])"
+    );
+}
@@ -0,0 +1,109 @@
+use proc_macro2::{Span, TokenStream, TokenTree};
+use quote::ToTokens;
+use quote_data::{with_span, QuoteIt, ToRustSource, ToTokensSpanned};
+
+#[derive(QuoteIt)]
+#[quote(span = origin)]
+struct Column {
+    name: String,
+    width: Option<u32>,
+    tags: Vec<&'static str>,
+    origin: proc_macro2::Span,
+}
+
+#[derive(QuoteIt)]
+struct Table {
+    columns: Vec<Column>,
+    strict: bool,
+}
+
+mod model {
+    use quote_data::QuoteIt;
+
+    #[derive(QuoteIt)]
+    pub struct Span {
+        pub start: u32,
+        pub end: u32,
+    }
+
+    #[derive(QuoteIt)]
+    pub struct Token {
+        pub span: Span,
+    }
+}
+
+fn span_at(line: usize) -> Span {
+    let source = format!("{}marker", "\n".repeat(line - 1));
+    let tokens: TokenStream = source.parse().unwrap();
+
+    tokens.into_iter().next().unwrap().span()
+}
+
+fn lines(tokens: TokenStream) -> Vec<usize> {
+    tokens
+        .into_iter()
+        .flat_map(|token| match token {
+            TokenTree::Group(group) => {
+                let mut lines = vec![group.span_open().start().line];
+                lines.extend(self::lines(group.stream()));
+                lines
+            }
+            token => vec![token.span().start().line],
+        })
+        .collect()
+}
+
+#[test]
+fn test_span_attribute() {
+    let column = Column {
+        name: "id".to_string(),
+        width: Some(8),
+        tags: vec!["key"],
+        origin: span_at(3),
+    };
+    let tokens = column.to_token_stream();
+
+    assert!(lines(tokens).iter().all(|line| *line == 3));
+}
+
+#[test]
+fn test_to_tokens_spanned() {
+    let table = Table {
+        columns: vec![Column {
+            name: "id".to_string(),
+            width: None,
+            tags: vec![],
+            origin: span_at(7),
+        }],
+        strict: true,
+    };
+    let tokens = table.to_token_stream_spanned(span_at(5));
+    let lines = lines(tokens);
+
+    assert!(lines.contains(&5));
+    assert!(lines.contains(&7));
+    assert!(lines.iter().all(|line| *line == 5 || *line == 7));
+}
+
+#[test]
+fn test_with_span_panic() {
+    let result = std::panic::catch_unwind(|| with_span(span_at(4), || panic!("quoting failed")));
+    assert!(result.is_err());
+
+    let table = Table {
+        columns: vec![],
+        strict: false,
+    };
+    assert!(!lines(table.to_token_stream()).contains(&4));
+}
+
+#[test]
+fn test_user_span_type() {
+    let token = model::Token {
+        span: model::Span { start: 1, end: 2 },
+    };
+    let expected = "Token::new(Span::new(1u32, 2u32))";
+
+    assert_eq!(token.to_token_stream().to_string().replace(' ', ""), expected.replace(' ', ""));
+    assert_eq!(token.to_rust_source(), expected);
+}
@@ -0,0 +1,55 @@
+use proc_macro2::TokenStream;
+use quote::ToTokens;
+use quote_data::{fill_holes, QuoteIt, Splice, ToRustSource};
+
+#[derive(QuoteIt)]
+struct Config {
+    name: String,
+    #[quote(raw)]
+    port: TokenStream,
+    home: Splice,
+    limit: Splice,
+}
+
+fn get_result<T: ToTokens>(value: T) -> String {
+    value
+        .to_token_stream()
+        .to_string()
+        .chars()
+        .filter(|c| !c.is_whitespace())
+        .collect()
+}
+
+fn config() -> Config {
+    Config {
+        name: "server".to_string(),
+        port: quote::quote! {port()},
+        home: Splice::new(quote::quote! {env!("HOME")}),
+        limit: Splice::hole("limit"),
+    }
+}
+
+#[test]
+fn test_splice_fields() {
+    assert_eq!(
+        get_result(config()),
+        "Config::new(\"server\".to_string(),port(),env!(\"HOME\"),#limit)"
+    );
+
+    let mut source = String::new();
+    config().write_source(&mut source).unwrap();
+    let source: String = source.chars().filter(|c| !c.is_whitespace()).collect();
+    assert_eq!(source, get_result(config()));
+}
+
+#[test]
+fn test_fill_holes() {
+    let tokens: TokenStream = "f(#a, [#b], #c)".parse().unwrap();
+    let filled = fill_holes(tokens, &mut |name| match name.to_string().as_str() {
+        "a" => Some(quote::quote! {1}),
+        "b" => Some(quote::quote! {2}),
+        _ => None,
+    });
+
+    assert_eq!(get_result(filled), "f(1,[2],#c)");
+}
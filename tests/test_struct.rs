@@ -47,7 +47,7 @@ fn test_unit() {
     let tuple = TestTuple(1, -1, vec![1, 2, 3]);
     assert_eq!(
         get_result(&tuple),
-        "TestTuple::new(1i32,-1i64,vec![1u8,2u8,3u8])"
+        "TestTuple::new(1i32,-1i64,b\"\\x01\\x02\\x03\".to_vec())"
     );
 }
 
@@ -83,3 +83,30 @@ fn test_struct() {
 
     let _ = quote::quote! {#st};
 }
+
+// Deliberately not `Clone`: fields are tokenized by reference.
+#[derive(QuoteIt)]
+struct Opaque(u8);
+
+#[derive(QuoteIt)]
+struct TestBorrowed {
+    opaque: Opaque,
+    list: Vec<Opaque>,
+    option: Option<Opaque>,
+    pair: (Opaque, String),
+}
+
+#[test]
+fn test_non_clone_fields() {
+    let st = TestBorrowed {
+        opaque: Opaque(1),
+        list: vec![Opaque(2), Opaque(3)],
+        option: Some(Opaque(4)),
+        pair: (Opaque(5), "five".to_string()),
+    };
+
+    assert_eq!(
+        get_result(&st),
+        "TestBorrowed::new(Opaque::new(1u8),vec![Opaque::new(2u8),Opaque::new(3u8)],Some(Opaque::new(4u8)),(Opaque::new(5u8),\"five\".to_string()))"
+    );
+}
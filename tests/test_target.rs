@@ -0,0 +1,108 @@
+use quote::ToTokens;
+use quote_data::{QuoteContext, QuoteIt, ToRustSource};
+
+#[derive(QuoteIt)]
+#[quote(target = "crate::runtime::RuntimeConfig")]
+struct Config {
+    #[quote(target_ty = "&'static str")]
+    name: String,
+    #[quote(target_ty = "&'static [&'static str]")]
+    hosts: Vec<String>,
+    #[quote(target_ty = "Option<&'static str>")]
+    region: Option<String>,
+    #[quote(target_ty = "&'static [u8]")]
+    key: Vec<u8>,
+    port: u16,
+    limits: Limits,
+}
+
+#[derive(QuoteIt)]
+#[quote(target = "crate::runtime::RuntimeLimits")]
+struct Limits(#[quote(target_ty = "Vec<&'static str>")] Vec<String>, u32);
+
+#[allow(dead_code)]
+#[derive(QuoteIt)]
+#[quote(target = "crate::runtime::RuntimeMode")]
+enum Mode {
+    Local,
+    Remote {
+        #[quote(target_ty = "&'static str")]
+        url: String,
+    },
+}
+
+#[derive(QuoteIt)]
+#[quote(target = "crate::runtime::RuntimeRoute")]
+struct Route {
+    #[quote(target_ty = "Box<Option<&'static str>>")]
+    name: Box<Option<String>>,
+    #[quote(target_ty = "Option<&'static [&'static str]>")]
+    methods: Option<Vec<String>>,
+    #[quote(target_ty = "&'static [Limits]")]
+    limits: Vec<Limits>,
+}
+
+fn strip(text: String) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn assert_quoted<T: ToTokens + ToRustSource>(value: &T, expected: &str) {
+    assert_eq!(strip(value.to_token_stream().to_string()), expected);
+
+    let mut source = String::new();
+    value.write_source(&mut source).unwrap();
+    assert_eq!(strip(source), expected);
+}
+
+fn config() -> Config {
+    Config {
+        name: "main".to_string(),
+        hosts: vec!["a".to_string(), "b".to_string()],
+        region: Some("eu".to_string()),
+        key: b"k".to_vec(),
+        port: 80,
+        limits: Limits(vec!["x".to_string()], 5),
+    }
+}
+
+#[test]
+fn test_target() {
+    assert_quoted(
+        &config(),
+        "crate::runtime::RuntimeConfig{name:\"main\",hosts:&[\"a\",\"b\"],region:Some(\"eu\"),\
+         key:b\"k\",port:80u16,limits:crate::runtime::RuntimeLimits(vec![\"x\"],5u32)}",
+    );
+}
+
+#[test]
+fn test_target_enum() {
+    assert_quoted(
+        &Mode::Remote {
+            url: "https://example.com".to_string(),
+        },
+        "crate::runtime::RuntimeMode::Remote{url:\"https://example.com\"}",
+    );
+    assert_quoted(&Mode::Local, "crate::runtime::RuntimeMode::Local");
+}
+
+#[test]
+fn test_target_path_rewrites() {
+    let context = QuoteContext::new().path("crate::runtime", "my_runtime");
+    let tokens = context.scope(|| Limits(vec![], 1).to_token_stream());
+    assert_eq!(strip(tokens.to_string()), "my_runtime::RuntimeLimits(vec![],1u32)");
+}
+
+#[test]
+fn test_target_nested() {
+    let route = Route {
+        name: Box::new(Some("index".to_string())),
+        methods: Some(vec!["GET".to_string()]),
+        limits: vec![Limits(vec![], 2)],
+    };
+
+    assert_quoted(
+        &route,
+        "crate::runtime::RuntimeRoute{name:::std::boxed::Box::new(Some(\"index\")),methods:Some(&[\"GET\"]),\
+         limits:&[crate::runtime::RuntimeLimits(vec![],2u32)]}",
+    );
+}
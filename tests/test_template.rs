@@ -0,0 +1,76 @@
+use quote::ToTokens;
+use quote_data::{QuoteIt, ToRustSource};
+
+#[derive(QuoteIt)]
+#[quote(template = "Endpoint::builder().host(#host).port(#port).build()")]
+struct Endpoint {
+    host: String,
+    port: u16,
+}
+
+#[derive(QuoteIt)]
+#[quote(template = "regex!(#0)")]
+struct Pattern(String);
+
+#[derive(QuoteIt)]
+#[quote(path = crate::net, template = "#Self::builder().routes(vec![#routes]).name(\"api\").build()")]
+struct Service {
+    routes: Vec<Pattern>,
+}
+
+#[derive(QuoteIt)]
+#[allow(dead_code)]
+#[quote(template = "Login::anonymous(#user)")]
+struct Login {
+    user: String,
+    #[quote(redact)]
+    password: String,
+}
+
+fn strip(text: String) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn assert_quoted<T: ToTokens + ToRustSource>(value: &T, expected: &str) {
+    assert_eq!(strip(value.to_token_stream().to_string()), expected);
+
+    let mut source = String::new();
+    value.write_source(&mut source).unwrap();
+    assert_eq!(strip(source), expected);
+}
+
+#[test]
+fn test_template() {
+    let endpoint = Endpoint {
+        host: "localhost".to_string(),
+        port: 80,
+    };
+
+    assert_quoted(
+        &endpoint,
+        "Endpoint::builder().host(\"localhost\".to_string()).port(80u16).build()",
+    );
+    assert_quoted(&Pattern("a+".to_string()), "regex!(\"a+\".to_string())");
+}
+
+#[test]
+fn test_template_self() {
+    let service = Service {
+        routes: vec![Pattern("/".to_string())],
+    };
+
+    assert_quoted(
+        &service,
+        "crate::net::Service::builder().routes(vec![vec![regex!(\"/\".to_string())]]).name(\"api\").build()",
+    );
+}
+
+#[test]
+fn test_template_skips_secret() {
+    let login = Login {
+        user: "guest".to_string(),
+        password: "secret-password".to_string(),
+    };
+
+    assert_quoted(&login, "Login::anonymous(\"guest\".to_string())");
+}
@@ -0,0 +1,51 @@
+use quote::ToTokens;
+use quote_data::{QuoteIt, ToRustSource};
+
+#[derive(QuoteIt)]
+#[quote(transparent)]
+struct UserId(u64);
+
+#[derive(QuoteIt)]
+#[quote(transparent)]
+struct Name {
+    value: String,
+}
+
+#[derive(QuoteIt)]
+#[quote(transparent)]
+enum Key {
+    Id(UserId),
+    Name { name: Name },
+}
+
+#[derive(QuoteIt)]
+struct User {
+    id: UserId,
+    keys: Vec<Key>,
+}
+
+fn strip(text: String) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+#[test]
+fn test_transparent() {
+    let user = User {
+        id: UserId(5),
+        keys: vec![
+            Key::Id(UserId(6)),
+            Key::Name {
+                name: Name {
+                    value: "root".to_string(),
+                },
+            },
+        ],
+    };
+    let expected = "User::new(5u64,vec![6u64,\"root\".to_string()])";
+
+    assert_eq!(strip(user.to_token_stream().to_string()), expected);
+
+    let mut source = String::new();
+    user.write_source(&mut source).unwrap();
+    assert_eq!(strip(source), expected);
+}
@@ -0,0 +1,143 @@
+use quote::ToTokens;
+use quote_data::{
+    in_field, try_quote, try_write_source, FloatPolicy, QuoteContext, QuoteError, QuoteIt,
+    ToRustSource, TryQuote,
+};
+use std::fmt::{self, Display};
+use std::str::FromStr;
+
+/// Address whose `Display` form only round-trips when it's lowercase.
+struct Address(String);
+
+impl Display for Address {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.0)
+    }
+}
+
+impl FromStr for Address {
+    type Err = ();
+
+    fn from_str(text: &str) -> Result<Self, ()> {
+        Ok(Address(text.to_lowercase()))
+    }
+}
+
+#[derive(QuoteIt)]
+struct Server {
+    #[quote(via_str = "const")]
+    addr: Address,
+    weight: f64,
+}
+
+#[derive(QuoteIt)]
+struct Config {
+    name: String,
+    servers: Vec<Server>,
+}
+
+fn server(addr: &str, weight: f64) -> Server {
+    Server {
+        addr: Address(addr.to_string()),
+        weight,
+    }
+}
+
+fn strip(text: String) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+#[test]
+fn test_try_quote() {
+    let config = Config {
+        name: "main".to_string(),
+        servers: vec![server("a", 1.0), server("b", 2.0)],
+    };
+
+    let tokens = config.try_to_tokens().unwrap();
+    assert_eq!(tokens.to_string(), config.to_token_stream().to_string());
+}
+
+#[test]
+fn test_try_quote_error_path() {
+    let config = Config {
+        name: "main".to_string(),
+        servers: vec![server("a", 1.0), server("b", 2.0), server("a", 1.0), server("B", 1.0)],
+    };
+
+    let error: QuoteError = config.try_to_tokens().unwrap_err();
+    assert_eq!(error.path(), "servers[3].addr");
+    assert_eq!(
+        error.message(),
+        "`Server.addr` doesn't round-trip through `FromStr`: \"B\" is parsed as \"b\""
+    );
+
+    assert_eq!(config.try_to_rust_source().unwrap_err(), error);
+}
+
+#[test]
+fn test_float_policy() {
+    let config = Config {
+        name: "main".to_string(),
+        servers: vec![server("a", f64::NAN)],
+    };
+
+    let tokens = config.to_token_stream();
+    assert!(strip(tokens.to_string()).contains("::core::f64::NAN"));
+
+    let mut source = String::new();
+    config.write_source(&mut source).unwrap();
+    assert_eq!(strip(source), strip(tokens.to_string()));
+
+    let strict = QuoteContext::new().floats(FloatPolicy::Strict);
+    let error = strict.clone().scope(|| config.try_to_tokens()).unwrap_err();
+    assert_eq!(error.path(), "servers[0].weight");
+    assert_eq!(
+        error.to_string(),
+        "`servers[0].weight`: `f64::NAN` can't be quoted under `FloatPolicy::Strict`"
+    );
+
+    let mut source = String::new();
+    let source_error = strict.scope(|| try_write_source(&config, &mut source)).unwrap_err();
+    assert_eq!(source_error, error);
+}
+
+#[test]
+#[should_panic(expected = "`servers[0].addr`: `Server.addr` doesn't round-trip")]
+fn test_to_tokens_panics_with_path() {
+    let config = Config {
+        name: "main".to_string(),
+        servers: vec![server("A", 1.0)],
+    };
+
+    config.to_token_stream();
+}
+
+#[test]
+fn test_try_quote_panic() {
+    let result = std::panic::catch_unwind(|| {
+        try_quote(|| in_field("servers", || panic!("quoting failed")))
+    });
+    assert!(result.is_err());
+
+    let config = Config {
+        name: "main".to_string(),
+        servers: vec![server("A", 1.0)],
+    };
+    let error = config.try_to_tokens().unwrap_err();
+    assert_eq!(error.path(), "servers[0].addr");
+
+    let result = std::panic::catch_unwind(|| config.to_token_stream());
+    assert!(result.is_err());
+}
+
+#[test]
+#[should_panic(expected = "`servers[0].addr`: `Server.addr` doesn't round-trip")]
+fn test_to_rust_source_panics_with_path() {
+    let config = Config {
+        name: "main".to_string(),
+        servers: vec![server("A", 1.0)],
+    };
+
+    config.to_rust_source();
+}
@@ -0,0 +1,118 @@
+use quote::ToTokens;
+use quote_data::{try_quote, QuoteContext, QuoteIt, ToRustSource, TryQuote};
+
+#[allow(dead_code)]
+#[derive(QuoteIt)]
+#[mod_path = "crate::level"]
+enum Level {
+    Info,
+    #[quote(rename = "Warning")]
+    Warn(u8),
+    #[quote(path = "crate::prelude")]
+    Error { code: u32 },
+    #[quote(path = "crate::prelude", rename = "Fatal")]
+    Panic,
+    #[quote(path = crate::prelude)]
+    Critical,
+    #[quote(skip)]
+    Trace(String),
+    #[quote(skip = "compile_error")]
+    Debug { message: String },
+}
+
+#[allow(dead_code)]
+#[derive(QuoteIt)]
+enum Source {
+    File(#[quote(via_str)] u16, #[quote(expr = "::core::default::Default::default()")] Vec<u8>),
+    Buffer {
+        #[quote(chunk = 2)]
+        bytes: Vec<u8>,
+        #[quote(env = "BUFFER_NAME")]
+        name: String,
+    },
+}
+
+fn strip(text: String) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn assert_quoted<T: ToTokens + ToRustSource>(value: &T, expected: &str) {
+    assert_eq!(strip(value.to_token_stream().to_string()), expected);
+
+    let mut source = String::new();
+    value.write_source(&mut source).unwrap();
+    assert_eq!(strip(source), expected);
+}
+
+#[test]
+fn test_rename() {
+    assert_quoted(&Level::Info, "crate::level::Level::Info");
+    assert_quoted(&Level::Warn(2), "crate::level::Level::Warning(2u8)");
+}
+
+#[test]
+fn test_variant_path() {
+    assert_quoted(&Level::Error { code: 3 }, "crate::prelude::Error{code:3u32}");
+    assert_quoted(&Level::Panic, "crate::prelude::Fatal");
+    assert_quoted(&Level::Critical, "crate::prelude::Critical");
+
+    let context = QuoteContext::new().path("crate::prelude", "::levels");
+    let tokens = context.scope(|| Level::Panic.to_token_stream());
+    assert_eq!(strip(tokens.to_string()), "::levels::Fatal");
+}
+
+#[test]
+fn test_skip() {
+    let error = Level::Trace("x".to_string()).try_to_tokens().unwrap_err();
+    assert_eq!(error.message(), "variant `Level::Trace` is skipped and can't be quoted");
+
+    let error = try_quote(|| {
+        let mut source = String::new();
+        assert!(Level::Trace("x".to_string()).write_source(&mut source).is_err());
+        proc_macro2::TokenStream::new()
+    })
+    .unwrap_err();
+    assert_eq!(error.message(), "variant `Level::Trace` is skipped and can't be quoted");
+
+    assert_quoted(
+        &Level::Debug {
+            message: "x".to_string(),
+        },
+        "::core::compile_error!(\"variant`Level::Debug`isskippedandcan'tbequoted\")",
+    );
+}
+
+#[test]
+#[should_panic(expected = "variant `Level::Trace` is skipped and can't be quoted")]
+fn test_skip_panics() {
+    Level::Trace("x".to_string()).to_token_stream();
+}
+
+#[test]
+fn test_skip_source_error() {
+    let mut source = String::new();
+    assert!(Level::Trace("x".to_string()).write_source(&mut source).is_err());
+}
+
+#[test]
+#[should_panic(expected = "variant `Level::Trace` is skipped and can't be quoted")]
+fn test_skip_source_panics() {
+    Level::Trace("x".to_string()).to_rust_source();
+}
+
+#[test]
+fn test_variant_field_attributes() {
+    assert_quoted(
+        &Source::File(80, vec![1]),
+        "Source::File(<u16as::core::str::FromStr>::from_str(\"80\").unwrap(),::core::default::Default::default())",
+    );
+    assert_quoted(
+        &Source::Buffer {
+            bytes: vec![1, 2, 3],
+            name: "main".to_string(),
+        },
+        "Source::Buffer{bytes:{letmutchunked=::std::vec::Vec::with_capacity(3usize);\
+         chunked.extend(vec![1u8,2u8]);chunked.extend(vec![3u8]);chunked},\
+         name:::core::env!(\"BUFFER_NAME\").to_string()}",
+    );
+}
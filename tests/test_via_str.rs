@@ -0,0 +1,86 @@
+use quote::ToTokens;
+use quote_data::{QuoteIt, ToRustSource};
+use std::fmt::{self, Display};
+use std::net::Ipv4Addr;
+use std::str::FromStr;
+
+/// A foreign-looking type with a string form but no public constructor.
+#[derive(QuoteIt)]
+#[quote(path = crate::mime, via_str = "const")]
+struct Mime {
+    essence: String,
+}
+
+impl Display for Mime {
+    fn fmt(&self, f: &mut fmt::Formatter) -> fmt::Result {
+        f.write_str(&self.essence)
+    }
+}
+
+impl FromStr for Mime {
+    type Err = ();
+
+    fn from_str(text: &str) -> Result<Self, ()> {
+        match text.split_once('/') {
+            Some(_) => Ok(Mime { essence: text.to_lowercase() }),
+            None => Err(()),
+        }
+    }
+}
+
+#[derive(QuoteIt)]
+struct Server {
+    #[quote(via_str)]
+    address: Ipv4Addr,
+    #[quote(via_str = "const")]
+    mime: Mime,
+}
+
+#[derive(QuoteIt)]
+enum Upstream {
+    Mime(#[quote(via_str = "const")] Mime),
+}
+
+fn strip(text: String) -> String {
+    text.chars().filter(|c| !c.is_whitespace()).collect()
+}
+
+fn assert_quoted<T: ToTokens + ToRustSource>(value: &T, expected: &str) {
+    assert_eq!(strip(value.to_token_stream().to_string()), expected);
+
+    let mut source = String::new();
+    value.write_source(&mut source).unwrap();
+    assert_eq!(strip(source), expected);
+}
+
+#[test]
+fn test_via_str() {
+    let server = Server {
+        address: Ipv4Addr::new(127, 0, 0, 1),
+        mime: Mime { essence: "text/html".to_string() },
+    };
+
+    assert_quoted(
+        &server,
+        "Server::new(<Ipv4Addras::core::str::FromStr>::from_str(\"127.0.0.1\").unwrap(),\
+         <Mimeas::core::str::FromStr>::from_str(\"text/html\").unwrap())",
+    );
+    assert_quoted(
+        &server.mime,
+        "<crate::mime::Mimeas::core::str::FromStr>::from_str(\"text/html\").unwrap()",
+    );
+}
+
+#[test]
+#[should_panic(expected = "`Upstream::Mime.0` doesn't round-trip through `FromStr`")]
+fn test_via_str_checked() {
+    let upstream = Upstream::Mime(Mime { essence: "Text/HTML".to_string() });
+
+    upstream.to_token_stream();
+}
+
+#[test]
+#[should_panic(expected = "`Mime` doesn't round-trip through `FromStr`: \"html\" can't be parsed")]
+fn test_via_str_unparsable() {
+    Mime { essence: "html".to_string() }.to_rust_source();
+}
@@ -0,0 +1,17 @@
+use quote_data::QuoteIt;
+
+#[derive(QuoteIt, Clone)]
+#[quote(into = "(String, String)")]
+struct Login {
+    user: String,
+    #[quote(redact)]
+    password: String,
+}
+
+impl From<Login> for (String, String) {
+    fn from(login: Login) -> Self {
+        (login.user, login.password)
+    }
+}
+
+fn main() {}
@@ -0,0 +1,11 @@
+use quote_data::QuoteIt;
+
+#[derive(QuoteIt)]
+#[quote(template = "connect(#host, #password)")]
+struct Connection {
+    host: String,
+    #[quote(redact)]
+    password: String,
+}
+
+fn main() {}
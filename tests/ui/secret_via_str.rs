@@ -0,0 +1,10 @@
+use quote_data::QuoteIt;
+
+#[derive(QuoteIt)]
+#[quote(via_str)]
+enum Source {
+    Env(#[quote(env = "API_KEY")] String),
+    Inline(String),
+}
+
+fn main() {}
@@ -0,0 +1,12 @@
+use quote_data::QuoteIt;
+
+#[derive(QuoteIt)]
+#[quote(target = "RuntimeConfig")]
+struct Config {
+    #[quote(target_ty = "RuntimeLimits")]
+    limits: Limits,
+}
+
+struct Limits(u32);
+
+fn main() {}